@@ -1,6 +1,9 @@
 use gloo_utils::format::JsValueSerdeExt;
-use gvas_save_parser::parse_save_data;
-use js_sys::{Promise, Uint8Array};
+use gvas_save_parser::{
+  parse_save_data_partial, parse_save_data_with, ParseMode, ParseOptions, SaveFile,
+  SaveFileParseError,
+};
+use js_sys::{Promise, Reflect, Uint8Array};
 use std::str;
 use wasm_bindgen::{prelude::*, JsCast};
 use wasm_bindgen_futures::JsFuture;
@@ -15,13 +18,169 @@ extern "C" {
 
 #[macro_export]
 macro_rules! console_log {
-  ($($t:tt)*) => (crate::log(&format_args!($($t)*).to_string()))
+  ($($t:tt)*) => ($crate::log(&format_args!($($t)*).to_string()))
 }
 
-#[wasm_bindgen]
+/**
+ * The struct types of the structs in DRG's maps and sets, none of which are
+ * recorded in the save. Every struct key and set element in a DRG save is the
+ * GUID of some game asset.
+ *
+ * Saves are parsed leniently, so that properties added by a game update which
+ * can't be parsed yet don't stop the rest of the save from loading.
+ */
+pub fn drg_parse_options() -> ParseOptions {
+  [
+    "CharacterSaves.ItemUpgradeLoadouts.Loadout.Key",
+    "CharacterSaves.RandomItemUpgradeLoadouts.Loadout.Key",
+    "EnemiesKilled.Key",
+    "ItemUpgradeLoadouts.Loadout.Key",
+    "Resources.OwnedResources.Key",
+    "SeasonSave.Seasons.Key",
+    "SeasonSave.Seasons.Value.CompletedSpecialChallenges.Key",
+    "UnLockedMissionParameters.Key",
+    "UnlockedItemSkins.Key",
+    "UnlockedItemSkins.Value.Skins",
+    "Drinks.UnlockedDrinks",
+    "FSDEventRewardsSave.EventsSeen",
+    "FSDEventRewardsSave.PopupsSeen",
+    "GameDLCSave.AnnouncedIDs",
+    "MinersManualKnownObjects",
+    "UnlockedPickaxeParts",
+  ]
+  .into_iter()
+  .fold(
+    ParseOptions::new().with_mode(ParseMode::Lenient),
+    |options, path| options.with_struct_type(path, "Guid"),
+  )
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const SAVE_PARSE_ERROR: &'static str = r#"
+export type SaveParseErrorKind =
+  | "UnexpectedEof"
+  | "InvalidMagic"
+  | "UnknownPropertyType"
+  | "UnknownTextHistoryType"
+  | "UnknownTextArgumentType"
+  | "InvalidUtf8"
+  | "InvalidUtf16"
+  | "InvalidBool"
+  | "InvalidStringLength"
+  | "SizeMismatch"
+  | "TrailingData"
+  | "Other";
+
+/**
+ * What `parse_save_file` rejects with when the save can't be parsed, and what
+ * the `errors` of a `PartialDrgSave` are.
+ */
+export interface SaveParseError extends Error {
+  name: "SaveParseError";
+  kind: SaveParseErrorKind;
+  /** The offset of the error from the start of the save. */
+  offset: number;
+  /** The path of the property which failed to parse, if the error was in one. */
+  propertyPath?: string;
+  /** A hex dump of the bytes around the error, with the byte at the offset in brackets. */
+  hexContext: string;
+}
+
+export interface SaveParseWarning {
+  offset: number;
+  path: string;
+  message: string;
+}
+
+/**
+ * What `parse_save_file` resolves with.
+ */
+export interface DrgSave {
+  metadata: Record<string, unknown>;
+  properties: Record<string, unknown>;
+  /** Everything which was not understood while parsing the save. */
+  warnings?: SaveParseWarning[];
+}
+
+/**
+ * What `parse_save_file_partial` resolves with.
+ */
+export interface PartialDrgSave extends DrgSave {
+  /** What failed to parse, if only part of the save could be parsed. */
+  errors?: SaveParseError[];
+}
+"#;
+
+/**
+ * Turns a parse error into a JS `Error` with the details of the error as
+ * properties, as described by `SaveParseError` above.
+ */
+fn to_js_error(e: &SaveFileParseError) -> JsValue {
+  let error = js_sys::Error::new(&e.message);
+  error.set_name("SaveParseError");
+  let set = |key: &str, value: JsValue| {
+    let _ = Reflect::set(&error, &JsValue::from(key), &value);
+  };
+  set("kind", <JsValue as JsValueSerdeExt>::from_serde(&e.kind).unwrap());
+  set("offset", JsValue::from(e.offset));
+  if let Some(property_path) = &e.property_path {
+    set("propertyPath", JsValue::from(property_path.to_string()));
+  }
+  set("hexContext", JsValue::from(e.hex_context.as_str()));
+  error.into()
+}
+
+/**
+ * Converts a parsed save to a JS value.
+ */
+fn to_js_save(save: &SaveFile) -> Result<JsValue, JsValue> {
+  <JsValue as JsValueSerdeExt>::from_serde(save)
+    .map_err(|e| JsValue::from(format!("Failed to convert save: {}", e)))
+}
+
+/**
+ * Parses a DRG save into a `DrgSave`. If it can't be parsed, this rejects
+ * with a `SaveParseError`, and if the file can't be read or the save can't be
+ * converted to a JS value, with a string.
+ */
+#[wasm_bindgen(unchecked_return_type = "DrgSave")]
 pub async fn parse_save_file(file: File) -> Result<JsValue, JsValue> {
   console_error_panic_hook::set_once();
+  let file_bytes = read_file(file).await?;
+
+  match parse_save_data_with(&file_bytes, &drg_parse_options()) {
+    Ok(s) => to_js_save(&s),
+    Err(e) => Err(to_js_error(&e)),
+  }
+}
 
+/**
+ * Parses a DRG save like `parse_save_file`, but if only part of the save can
+ * be parsed, resolves with that part as a `PartialDrgSave`. Its `errors` are
+ * the properties which failed to parse, and what stopped the rest of the save
+ * from being parsed, if anything did. This only rejects with a
+ * `SaveParseError` if none of the save can be parsed.
+ */
+#[wasm_bindgen(unchecked_return_type = "PartialDrgSave")]
+pub async fn parse_save_file_partial(file: File) -> Result<JsValue, JsValue> {
+  console_error_panic_hook::set_once();
+  let file_bytes = read_file(file).await?;
+
+  match parse_save_data_partial(&file_bytes, &drg_parse_options()) {
+    Ok(s) => to_js_save(&s),
+    Err(e) => match &e.save {
+      Some(s) => {
+        let save = to_js_save(s)?;
+        let errors: js_sys::Array = e.errors.iter().map(to_js_error).collect();
+        let _ = Reflect::set(&save, &JsValue::from("errors"), &errors);
+        Ok(save)
+      }
+      None => Err(to_js_error(&e.errors[0])),
+    },
+  }
+}
+
+async fn read_file(file: File) -> Result<Vec<u8>, JsValue> {
   // The FileReader API is callback-based, so we call it inside of a Promise
   // which we convert to a Rust future for usgae with async.
   let p = Promise::new(&mut |resolve, reject| {
@@ -40,21 +199,14 @@ pub async fn parse_save_file(file: File) -> Result<JsValue, JsValue> {
   // return "Option" so we need a lot of casting and chaining to get the value
   // actually out of the fututre.
   // In JS this would look like `e.target().result()`
-  let file_bytes = match JsCast::dyn_ref::<ProgressEvent>(&JsFuture::from(p).await?)
-    .map(|progress_event| progress_event.target())
-    .flatten()
+  match JsCast::dyn_ref::<ProgressEvent>(&JsFuture::from(p).await?)
+    .and_then(|progress_event| progress_event.target())
     .as_deref()
-    .map(|target| JsCast::dyn_ref::<FileReader>(target))
-    .flatten()
+    .and_then(|target| JsCast::dyn_ref::<FileReader>(target))
     .map(|file_reader| file_reader.result())
     .map(|result| result.map(|v| Uint8Array::new(&v).to_vec()))
   {
     Some(e) => e,
     _ => Err(JsValue::from("Error getting data from file reader promise")),
-  }?;
-
-  match parse_save_data(&file_bytes) {
-    Ok(s) => Ok(<JsValue as JsValueSerdeExt>::from_serde(&s).unwrap()),
-    Err(e) => Err(JsValue::from(e.to_string())),
   }
 }
@@ -1,21 +1,177 @@
 mod parser;
+mod writer;
 
-use nom::Finish;
-use parser::root;
+use nom::{Err, Finish};
+use parser::{root, InputError, ParseState};
+use std::{cell::RefCell, rc::Rc};
+use writer::write_root;
 
-use parser::{SaveFile, SaveFileParseError};
+pub use parser::{
+  custom_version_guid, custom_version_name, ArchiveVersion, ArrayProperty, ArrayPropertyValue,
+  ArrayPropertyValues, ArrayStructTag, BoundingBox, BytePropertyValue, Color, Delegate, FText,
+  FormatArgumentValue, Guid, IntPoint, IntVector, LinearColor, MapProperty, MapPropertyKey,
+  MapPropertyValue, Metadata, NumberFormattingOptions, ParseErrorKind, ParseMode, ParseOptions,
+  ParseTraceEntry, ParseWarning, PartialParseError, PathSegment, Property, PropertyMap,
+  PropertyMapIter, PropertyPath, PropertyTag, Quat, Rotator, SaveFile, SaveFileParseError,
+  SetProperty, SetPropertyValue, SoftObjectPath, StructPropertyValue, TaggedProperty, TextHistory,
+  Transform, Vector, Vector2D, Vector4, KNOWN_CUSTOM_VERSIONS,
+};
 
-pub fn parse_save_data(input: &[u8]) -> Result<SaveFile, SaveFileParseError> {
-  let (_, save_file) = root(input).finish()?;
-  Ok(save_file)
+pub fn parse_save_data(input: &[u8]) -> Result<SaveFile, Box<SaveFileParseError>> {
+  parse_save_data_with(input, &ParseOptions::default())
+}
+
+pub fn parse_save_data_with(
+  input: &[u8],
+  options: &ParseOptions,
+) -> Result<SaveFile, Box<SaveFileParseError>> {
+  parse(input, options, false).map_err(|mut e| Box::new(e.errors.swap_remove(0)))
+}
+
+/**
+ * Parses a save like `parse_save_data_with`, but if it fails to parse,
+ * returns what was parsed of it along with the errors. A root property which
+ * fails to parse is skipped, using the size declared in its tag, so that the
+ * properties after it are still parsed.
+ */
+pub fn parse_save_data_partial(
+  input: &[u8],
+  options: &ParseOptions,
+) -> Result<SaveFile, Box<PartialParseError>> {
+  parse(input, options, true)
+}
+
+fn parse(
+  input: &[u8],
+  options: &ParseOptions,
+  skip_failed: bool,
+) -> Result<SaveFile, Box<PartialParseError>> {
+  let state = Rc::new(RefCell::new(ParseState::default()));
+  let result = root(Rc::new(options.clone()), state.clone(), skip_failed)(input).finish();
+  match result {
+    Ok((_, (save_file, failures))) if failures.is_empty() => Ok(save_file),
+    Ok((_, (save_file, failures))) => {
+      let errors = failures
+        .into_iter()
+        .map(|failure| {
+          let e: InputError = match failure.error {
+            Err::Error(e) | Err::Failure(e) => e,
+            Err::Incomplete(_) => unreachable!("the parsers only read complete input"),
+          };
+          let trace = save_file.trace[..failure.trace_len].to_vec();
+          e.into_owned(input, failure.property_path, trace)
+        })
+        .collect();
+      Err(Box::new(PartialParseError {
+        save: Some(save_file),
+        errors,
+      }))
+    }
+    Err(e) => {
+      let e: InputError = e;
+      let state = state.take();
+      Err(Box::new(PartialParseError {
+        save: None,
+        errors: vec![e.into_owned(input, state.failure_path, state.trace)],
+      }))
+    }
+  }
+}
+
+/**
+ * Writes a save back out. A parsed save is written back byte for byte, except
+ * for strings stored in an encoding the engine doesn't write them in, which
+ * are written in the one it does.
+ */
+pub fn write_save_data(save_file: &SaveFile) -> Vec<u8> {
+  let mut out = vec![];
+  write_root(&mut out, save_file);
+  out
 }
 
 #[cfg(test)]
 mod tests {
+  use crate::{
+    ArrayProperty, ArrayPropertyValue, ArrayPropertyValues, ArrayStructTag, BoundingBox,
+    BytePropertyValue, Color, FText, FormatArgumentValue, Guid, IntPoint, IntVector, LinearColor,
+    MapProperty, MapPropertyKey, MapPropertyValue, Metadata, ParseErrorKind, ParseMode,
+    ParseOptions, PathSegment, Property, PropertyMap, PropertyTag, Quat, Rotator, SaveFile,
+    SetProperty, SetPropertyValue, SoftObjectPath, StructPropertyValue, TaggedProperty,
+    TextHistory, Transform, Vector, Vector2D, Vector4,
+  };
   use std::{fs, path::Path};
 
-  #[test]
-  fn it_successfully_parses_saves() {
+  fn tagged(value: Property) -> TaggedProperty {
+    TaggedProperty {
+      tag: PropertyTag::default(),
+      value,
+    }
+  }
+
+  /**
+   * Writes the given properties out under the metadata of a real save, then
+   * checks that parsing them back and writing them again changes nothing.
+   */
+  fn assert_round_trips(properties: Vec<(&str, Property)>) {
+    assert_round_trips_with(save_1_metadata(), properties);
+  }
+
+  fn save_1_metadata() -> Metadata {
+    let save_file_data = fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/test/save_1.sav")).unwrap();
+    crate::parse_save_data(&save_file_data).unwrap().metadata
+  }
+
+  /**
+   * A save with the metadata of a real save and nothing but the given
+   * properties.
+   */
+  fn save_with(properties: Vec<(&str, Property)>) -> SaveFile {
+    SaveFile {
+      metadata: save_1_metadata(),
+      properties: properties
+        .into_iter()
+        .map(|(name, value)| (name.to_owned(), tagged(value)))
+        .collect::<PropertyMap>(),
+      trailer: vec![0; 4],
+      warnings: vec![],
+      trace: vec![],
+    }
+  }
+
+  fn assert_round_trips_with(metadata: Metadata, properties: Vec<(&str, Property)>) -> SaveFile {
+    let save_file = SaveFile {
+      metadata,
+      ..save_with(properties)
+    };
+
+    let written = crate::write_save_data(&save_file);
+    let parsed = crate::parse_save_data(&written).unwrap();
+    assert_eq!(
+      serde_json::to_string(&save_file).unwrap(),
+      serde_json::to_string(&parsed).unwrap()
+    );
+    assert_eq!(written, crate::write_save_data(&parsed));
+    parsed
+  }
+
+  /**
+   * Makes the int property with the given name declare a size of 5 bytes,
+   * and adds an extra byte after its value to match. Returns the new save and
+   * the offset of the size.
+   */
+  fn oversize_int(save: &[u8], name: &str) -> (Vec<u8>, usize) {
+    let mut oversized = save.to_vec();
+    let name = [name.as_bytes(), b"\0"].concat();
+    let size_offset = save.windows(name.len()).position(|w| w == name).unwrap()
+      + name.len()
+      + 4
+      + "IntProperty\0".len();
+    oversized[size_offset] = 5;
+    oversized.insert(size_offset + 4 + 4 + 1 + 4, 0);
+    (oversized, size_offset)
+  }
+
+  fn read_save_files() -> Vec<(String, Vec<u8>)> {
     let save_files = fs::read_dir(Path::new(concat!(
       env!("CARGO_MANIFEST_DIR"),
       "/",
@@ -24,12 +180,19 @@ mod tests {
     )))
     .unwrap();
 
-    for save_file in save_files {
-      let save_file = save_file.unwrap();
-      let save_file_path = save_file.path();
-      let save_file_name = save_file_path.file_name().unwrap().to_str().unwrap();
-      let save_file_data = fs::read(&save_file_path).expect("Failed to read save file");
+    save_files
+      .map(|save_file| {
+        let save_file_path = save_file.unwrap().path();
+        let save_file_name = save_file_path.file_name().unwrap().to_str().unwrap();
+        let save_file_data = fs::read(&save_file_path).expect("Failed to read save file");
+        (save_file_name.to_owned(), save_file_data)
+      })
+      .collect()
+  }
 
+  #[test]
+  fn it_successfully_parses_saves() {
+    for (save_file_name, save_file_data) in read_save_files() {
       println!("Parsing save file {}", save_file_name);
       match crate::parse_save_data(&save_file_data) {
         Ok(_) => (),
@@ -37,4 +200,1352 @@ mod tests {
       }
     }
   }
+
+  #[test]
+  fn it_serializes_saves_in_file_order() {
+    for (save_file_name, save_file_data) in read_save_files() {
+      let save_file = crate::parse_save_data(&save_file_data).unwrap();
+      let json = serde_json::to_string(&save_file).unwrap();
+      let reparsed = crate::parse_save_data(&save_file_data).unwrap();
+      assert_eq!(
+        json,
+        serde_json::to_string(&reparsed).unwrap(),
+        "{}",
+        save_file_name
+      );
+
+      let (first_name, _) = save_file.properties.iter().next().unwrap();
+      let properties_json = &json[json.find("\"properties\":{").unwrap()..];
+      assert!(properties_json.starts_with(&format!("\"properties\":{{\"{}\"", first_name)));
+    }
+  }
+
+  #[test]
+  fn it_keeps_every_static_array_element() {
+    let save_file_data = fs::read(concat!(
+      env!("CARGO_MANIFEST_DIR"),
+      "/test/static_array.sav"
+    ))
+    .unwrap();
+    let save_file = crate::parse_save_data(&save_file_data).unwrap();
+
+    let ints = save_file.properties.get_all("StaticInts");
+    assert_eq!(ints.len(), 3);
+    for (i, (property, expected)) in ints.iter().zip([7, 8, 9]).enumerate() {
+      assert_eq!(property.tag.array_index, i as u32);
+      assert!(matches!(property.value, Property::Int(v) if v == expected));
+    }
+
+    let bools = save_file.properties.get_all("StaticBools");
+    assert_eq!(bools.len(), 2);
+    assert!(matches!(bools[0].value, Property::Bool(true)));
+    assert!(matches!(bools[1].value, Property::Bool(false)));
+
+    let json = serde_json::to_string(&save_file.properties).unwrap();
+    assert!(json.contains("\"StaticInts\":[7,8,9]"));
+    assert!(json.contains("\"Name\":\"Karl\""));
+
+    // Elements are replaced by index, whether they are collected or inserted
+    let element = |array_index, value| TaggedProperty {
+      tag: PropertyTag {
+        array_index,
+        ..PropertyTag::default()
+      },
+      value: Property::Int(value),
+    };
+    let mut properties: PropertyMap = [(1, 1), (3, 3), (1, 5)]
+      .into_iter()
+      .map(|(array_index, value)| ("Ints".to_owned(), element(array_index, value)))
+      .collect();
+    let replaced = properties.insert("Ints".to_owned(), element(3, 10));
+    assert!(matches!(replaced.unwrap().value, Property::Int(3)));
+    properties.insert("Ints".to_owned(), element(2, 2));
+    let elements: Vec<(u32, &Property)> = properties
+      .get_all("Ints")
+      .iter()
+      .map(|p| (p.tag.array_index, &p.value))
+      .collect();
+    assert!(matches!(
+      elements[..],
+      [
+        (1, Property::Int(5)),
+        (2, Property::Int(2)),
+        (3, Property::Int(10))
+      ]
+    ));
+  }
+
+  #[test]
+  fn it_writes_saves_byte_for_byte() {
+    for (save_file_name, save_file_data) in read_save_files() {
+      println!("Writing save file {}", save_file_name);
+      let save_file = crate::parse_save_data(&save_file_data).unwrap();
+      let written = crate::write_save_data(&save_file);
+      if let Some(i) =
+        (0..written.len().min(save_file_data.len())).find(|&i| written[i] != save_file_data[i])
+      {
+        panic!("Save file {} differs at byte {}", save_file_name, i);
+      }
+      assert_eq!(written.len(), save_file_data.len(), "{}", save_file_name);
+    }
+  }
+
+  #[test]
+  fn it_normalizes_string_encodings() {
+    let save_file = save_with(vec![("Name", Property::Str("Karl".to_owned()))]);
+    let written = crate::write_save_data(&save_file);
+    let narrow = [&5i32.to_le_bytes()[..], b"Karl\0"].concat();
+    let value = written
+      .windows(narrow.len())
+      .position(|w| w == narrow)
+      .unwrap();
+
+    // The same string stored as a wide string, and an empty string stored
+    // with a null-terminator, are read as the same strings but written back
+    // in the narrowest encoding
+    let wide = [&(-5i32).to_le_bytes()[..], b"K\0a\0r\0l\0\0\0"].concat();
+    let terminated_empty = [&1i32.to_le_bytes()[..], b"\0"].concat();
+    for (encoded, expected) in [(wide, "Karl"), (terminated_empty, "")] {
+      let mut save = [
+        &written[..value],
+        &encoded[..],
+        &written[value + narrow.len()..],
+      ]
+      .concat();
+      // The size of the value is the u32 before the array index and the guid
+      // flag
+      let size_offset = value - 4 - 4 - 1;
+      save[size_offset..size_offset + 4].copy_from_slice(&(encoded.len() as u32).to_le_bytes());
+
+      let parsed = crate::parse_save_data(&save).unwrap();
+      assert!(matches!(
+        &parsed.properties.get("Name").unwrap().value,
+        Property::Str(name) if name == expected
+      ));
+      let rewritten = crate::write_save_data(&parsed);
+      assert_ne!(rewritten, save);
+      assert_eq!(
+        rewritten,
+        crate::write_save_data(&save_with(vec![(
+          "Name",
+          Property::Str(expected.to_owned())
+        )]))
+      );
+    }
+  }
+
+  #[test]
+  fn it_round_trips_byte_properties() {
+    assert_round_trips(vec![
+      (
+        "Byte",
+        Property::Byte {
+          name: "None".to_owned(),
+          value: BytePropertyValue::Byte(3),
+        },
+      ),
+      (
+        "EnumByte",
+        Property::Byte {
+          name: "EDwarfClass".to_owned(),
+          value: BytePropertyValue::Label("EDwarfClass::Driller".to_owned()),
+        },
+      ),
+      (
+        "Bytes",
+        Property::Array(ArrayProperty {
+          element_type: "ByteProperty".to_owned(),
+          struct_tag: None,
+          values: ArrayPropertyValues::Bytes(vec![1, 2]),
+        }),
+      ),
+      (
+        "EnumBytes",
+        Property::Array(ArrayProperty {
+          element_type: "ByteProperty".to_owned(),
+          struct_tag: None,
+          values: ArrayPropertyValues::Elements(vec![ArrayPropertyValue::Byte(
+            BytePropertyValue::Label("EDwarfClass::Scout".to_owned()),
+          )]),
+        }),
+      ),
+      (
+        "ByteMap",
+        Property::Map(MapProperty {
+          key_type: "ByteProperty".to_owned(),
+          value_type: "ByteProperty".to_owned(),
+          keys_to_remove: vec![],
+          entries: [(
+            MapPropertyKey::Byte(BytePropertyValue::Byte(4)),
+            MapPropertyValue::Byte(BytePropertyValue::Byte(5)),
+          )]
+          .into_iter()
+          .collect(),
+        }),
+      ),
+      (
+        "ByteSet",
+        Property::Set(SetProperty {
+          element_type: "ByteProperty".to_owned(),
+          elements_to_remove: vec![],
+          elements: vec![
+            SetPropertyValue::Byte(BytePropertyValue::Byte(6)),
+            SetPropertyValue::Byte(BytePropertyValue::Byte(7)),
+          ],
+        }),
+      ),
+      (
+        "EnumByteSet",
+        Property::Set(SetProperty {
+          element_type: "ByteProperty".to_owned(),
+          elements_to_remove: vec![],
+          elements: vec![SetPropertyValue::Byte(BytePropertyValue::Label(
+            "EDwarfClass::Gunner".to_owned(),
+          ))],
+        }),
+      ),
+    ]);
+
+    // Plain byte arrays are read back as one blob
+    let parsed = assert_round_trips_with(
+      save_1_metadata(),
+      vec![(
+        "Bytes",
+        Property::Array(ArrayProperty {
+          element_type: "ByteProperty".to_owned(),
+          struct_tag: None,
+          values: ArrayPropertyValues::Elements(vec![
+            ArrayPropertyValue::Byte(BytePropertyValue::Byte(1)),
+            ArrayPropertyValue::Byte(BytePropertyValue::Byte(2)),
+          ]),
+        }),
+      )],
+    );
+    assert!(matches!(
+      &parsed.properties.get("Bytes").unwrap().value,
+      Property::Array(ArrayProperty {
+        values: ArrayPropertyValues::Bytes(bytes),
+        ..
+      }) if bytes == &[1, 2]
+    ));
+  }
+
+  #[test]
+  fn it_reads_enum_bytes_in_maps() {
+    let label = |name: &str| BytePropertyValue::Label(name.to_owned());
+    let byte_map = |value_type: &str, value| {
+      Property::Map(MapProperty {
+        key_type: "ByteProperty".to_owned(),
+        value_type: value_type.to_owned(),
+        keys_to_remove: vec![],
+        entries: [(MapPropertyKey::Byte(label("EDwarfClass::Engineer")), value)]
+          .into_iter()
+          .collect(),
+      })
+    };
+
+    // Maps of fixed size keys and values are told apart by their size, as
+    // byte arrays and sets are
+    assert_round_trips(vec![
+      (
+        "ClassLevels",
+        byte_map("ByteProperty", MapPropertyValue::Byte(label("ERank::Gold"))),
+      ),
+      (
+        "ClassXp",
+        byte_map("IntProperty", MapPropertyValue::Int(1500)),
+      ),
+    ]);
+
+    // Otherwise an enum type is needed
+    let save_file = save_with(vec![(
+      "ClassNames",
+      byte_map("StrProperty", MapPropertyValue::Str("Engie".to_owned())),
+    )]);
+    let written = crate::write_save_data(&save_file);
+    assert!(crate::parse_save_data(&written).is_err());
+
+    let options = ParseOptions::new().with_enum_type("ClassNames.Key", "EDwarfClass");
+    let parsed = crate::parse_save_data_with(&written, &options).unwrap();
+    assert_eq!(
+      serde_json::to_string(&save_file).unwrap(),
+      serde_json::to_string(&parsed).unwrap()
+    );
+    assert_eq!(written, crate::write_save_data(&parsed));
+  }
+
+  #[test]
+  fn it_round_trips_numeric_properties() {
+    assert_round_trips(vec![
+      ("Int8", Property::Int8(-8)),
+      ("Int16", Property::Int16(-16)),
+      ("Int64", Property::Int64(-64)),
+      ("UInt16", Property::UInt16(16)),
+      ("UInt64", Property::UInt64(u64::MAX)),
+      ("Double", Property::Double(0.1)),
+      (
+        "Doubles",
+        Property::Array(ArrayProperty {
+          element_type: "DoubleProperty".to_owned(),
+          struct_tag: None,
+          values: ArrayPropertyValues::Elements(vec![
+            ArrayPropertyValue::Double(1.5),
+            ArrayPropertyValue::Double(-2.5),
+          ]),
+        }),
+      ),
+      (
+        "Int16s",
+        Property::Set(SetProperty {
+          element_type: "Int16Property".to_owned(),
+          elements_to_remove: vec![],
+          elements: vec![SetPropertyValue::Int16(1), SetPropertyValue::Int16(2)],
+        }),
+      ),
+      (
+        "FloatToUInt64",
+        Property::Map(MapProperty {
+          key_type: "FloatProperty".to_owned(),
+          value_type: "UInt64Property".to_owned(),
+          keys_to_remove: vec![],
+          entries: vec![(MapPropertyKey::Float(0.5), MapPropertyValue::UInt64(5))],
+        }),
+      ),
+    ]);
+  }
+
+  #[test]
+  fn it_round_trips_text_properties() {
+    let base = |source_string: &str| FText {
+      flags: 0,
+      history: TextHistory::Base {
+        namespace: "".to_owned(),
+        key: "6A1C3F2B4E0D".to_owned(),
+        source_string: source_string.to_owned(),
+      },
+    };
+
+    assert_round_trips(vec![
+      (
+        "Invariant",
+        Property::Text(FText {
+          flags: 2,
+          history: TextHistory::None {
+            culture_invariant_string: Some("Rock and Stone".to_owned()),
+          },
+        }),
+      ),
+      (
+        "Empty",
+        Property::Text(FText {
+          flags: 0,
+          history: TextHistory::None {
+            culture_invariant_string: None,
+          },
+        }),
+      ),
+      ("Base", Property::Text(base("Karl"))),
+      (
+        "Ordered",
+        Property::Text(FText {
+          flags: 0,
+          history: TextHistory::OrderedFormat {
+            source_format: Box::new(base("{0} of {1}")),
+            arguments: vec![
+              FormatArgumentValue::Int(3),
+              FormatArgumentValue::Text(Box::new(base("Morkite"))),
+            ],
+          },
+        }),
+      ),
+      (
+        "Number",
+        Property::Text(FText {
+          flags: 0,
+          history: TextHistory::AsNumber {
+            source_value: FormatArgumentValue::Double(1.5),
+            format_options: Some(crate::NumberFormattingOptions {
+              always_sign: false,
+              use_grouping: true,
+              rounding_mode: 0,
+              minimum_integral_digits: 1,
+              maximum_integral_digits: 324,
+              minimum_fractional_digits: 0,
+              maximum_fractional_digits: 3,
+            }),
+            target_culture: "en".to_owned(),
+          },
+        }),
+      ),
+      (
+        "Date",
+        Property::Text(FText {
+          flags: 0,
+          history: TextHistory::AsDate {
+            source_date_time: 637_000_000_000_000_000,
+            date_style: 2,
+            time_zone: "UTC".to_owned(),
+            target_culture: "".to_owned(),
+          },
+        }),
+      ),
+      (
+        "TableEntry",
+        Property::Text(FText {
+          flags: 0,
+          history: TextHistory::StringTableEntry {
+            table_id: "/Game/UI/Strings".to_owned(),
+            key: "Dwarf".to_owned(),
+          },
+        }),
+      ),
+    ]);
+
+    let ordered = save_with(vec![(
+      "Ordered",
+      Property::Text(FText {
+        flags: 0,
+        history: TextHistory::OrderedFormat {
+          source_format: Box::new(base("{0}")),
+          arguments: vec![FormatArgumentValue::Int(3)],
+        },
+      }),
+    )]);
+    let mut written = crate::write_save_data(&ordered);
+    let source = written.windows(4).position(|w| w == b"{0}\0").unwrap();
+    // The argument type follows the source format and the argument count
+    written[source + 4 + 4] = 9;
+    let error = crate::parse_save_data(&written).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::UnknownTextArgumentType);
+    assert!(error.message.contains("type 9"));
+  }
+
+  #[test]
+  fn it_round_trips_soft_object_properties() {
+    let path = |asset_path_name: &str, sub_path_string: &str| SoftObjectPath {
+      asset_path_name: asset_path_name.to_owned(),
+      sub_path_string: sub_path_string.to_owned(),
+    };
+
+    assert_round_trips(vec![
+      (
+        "SoftObject",
+        Property::SoftObject(path("/Game/Cosmetics/Beard.Beard", "")),
+      ),
+      (
+        "SoftClassPath",
+        Property::SoftClassPath(path("/Game/Dwarves/Scout.Scout_C", "Pickaxe")),
+      ),
+      ("LazyObject", Property::LazyObject(Guid([1, 2, 3, 4]))),
+      (
+        "Interface",
+        Property::Interface("/Game/Maps/Space.Space:Rig".to_owned()),
+      ),
+      (
+        "SoftObjects",
+        Property::Array(ArrayProperty {
+          element_type: "SoftObjectProperty".to_owned(),
+          struct_tag: None,
+          values: ArrayPropertyValues::Elements(vec![
+            ArrayPropertyValue::SoftObject(path("/Game/Paint/Red.Red", "")),
+            ArrayPropertyValue::SoftObject(path("/Game/Paint/Blue.Blue", "")),
+          ]),
+        }),
+      ),
+      (
+        "Loadout",
+        Property::Map(MapProperty {
+          key_type: "SoftClassPathProperty".to_owned(),
+          value_type: "SoftObjectProperty".to_owned(),
+          keys_to_remove: vec![],
+          entries: vec![(
+            MapPropertyKey::SoftClassPath(path("/Game/Weapons/Shotgun.Shotgun_C", "")),
+            MapPropertyValue::SoftObject(path("/Game/Frameworks/Gold.Gold", "")),
+          )],
+        }),
+      ),
+    ]);
+  }
+
+  #[test]
+  fn it_round_trips_math_structs() {
+    let vector = |x, y, z| Vector { x, y, z };
+    let quat = Quat {
+      x: 0.0,
+      y: 0.0,
+      z: 0.5,
+      w: 0.5,
+    };
+
+    assert_round_trips(vec![
+      (
+        "Location",
+        Property::Struct(StructPropertyValue::Vector(vector(1.0, -2.0, 3.5))),
+      ),
+      (
+        "Offset",
+        Property::Struct(StructPropertyValue::Vector2D(Vector2D { x: 0.5, y: 1.5 })),
+      ),
+      (
+        "Plane",
+        Property::Struct(StructPropertyValue::Vector4(Vector4 {
+          x: 1.0,
+          y: 2.0,
+          z: 3.0,
+          w: 4.0,
+        })),
+      ),
+      (
+        "Rotation",
+        Property::Struct(StructPropertyValue::Rotator(Rotator {
+          pitch: 10.0,
+          yaw: 90.0,
+          roll: 0.0,
+        })),
+      ),
+      (
+        "Orientation",
+        Property::Struct(StructPropertyValue::Quat(quat)),
+      ),
+      (
+        "ArmorPaint",
+        Property::Struct(StructPropertyValue::LinearColor(LinearColor {
+          r: 0.8,
+          g: 0.4,
+          b: 0.1,
+          a: 1.0,
+        })),
+      ),
+      (
+        "BeardColor",
+        Property::Struct(StructPropertyValue::Color(Color {
+          r: 200,
+          g: 100,
+          b: 50,
+          a: 255,
+        })),
+      ),
+      (
+        "Cell",
+        Property::Struct(StructPropertyValue::IntPoint(IntPoint { x: -1, y: 2 })),
+      ),
+      (
+        "Chunk",
+        Property::Struct(StructPropertyValue::IntVector(IntVector {
+          x: 1,
+          y: 2,
+          z: 3,
+        })),
+      ),
+      (
+        "Bounds",
+        Property::Struct(StructPropertyValue::Box(BoundingBox {
+          min: vector(-1.0, -1.0, -1.0),
+          max: vector(1.0, 1.0, 1.0),
+          is_valid: true,
+        })),
+      ),
+      (
+        "MissionTime",
+        Property::Struct(StructPropertyValue::Timespan(36_000_000_000)),
+      ),
+      (
+        "Spawn",
+        Property::Struct(StructPropertyValue::Transform(Transform {
+          rotation: quat,
+          translation: vector(100.0, 200.0, 300.0),
+          scale_3d: vector(1.0, 1.0, 1.0),
+        })),
+      ),
+      (
+        "Waypoints",
+        Property::Array(ArrayProperty {
+          element_type: "StructProperty".to_owned(),
+          struct_tag: Some(ArrayStructTag {
+            name: "Waypoints".to_owned(),
+            struct_type: "Vector".to_owned(),
+            tag: PropertyTag::default(),
+          }),
+          values: ArrayPropertyValues::Elements(vec![
+            ArrayPropertyValue::Struct(StructPropertyValue::Vector(vector(0.0, 0.0, 0.0))),
+            ArrayPropertyValue::Struct(StructPropertyValue::Vector(vector(1.0, 1.0, 1.0))),
+          ]),
+        }),
+      ),
+    ]);
+  }
+
+  #[test]
+  fn it_round_trips_every_save_game_version_header() {
+    let properties = || vec![("Level", Property::Int(7))];
+
+    let mut metadata = save_1_metadata();
+    metadata.save_version = 1;
+    metadata.custom_format_version = None;
+    metadata.custom_format_data.clear();
+    let parsed = assert_round_trips_with(metadata, properties());
+    assert_eq!(parsed.metadata.custom_format_version, None);
+
+    let mut metadata = save_1_metadata();
+    metadata.save_version = 3;
+    metadata.package_version_ue5 = Some(1003);
+    metadata.engine_major = 5;
+    assert!(!metadata.archive_version().large_world_coordinates);
+    let parsed = assert_round_trips_with(metadata, properties());
+    assert_eq!(parsed.metadata.package_version_ue5, Some(1003));
+
+    let mut metadata = save_1_metadata();
+    metadata.save_version = 3;
+    metadata.package_version_ue5 = Some(1009);
+    assert!(metadata.archive_version().large_world_coordinates);
+    assert_round_trips_with(metadata, properties());
+  }
+
+  #[test]
+  fn it_looks_up_custom_versions_by_name() {
+    let metadata = save_1_metadata();
+    assert_eq!(metadata.custom_version("FReleaseObjectVersion"), Some(43));
+    assert_eq!(metadata.custom_version("FEditorObjectVersion"), Some(40));
+    assert_eq!(metadata.custom_version("FUE5MainStreamObjectVersion"), None);
+    assert_eq!(metadata.custom_version("FNotAVersion"), None);
+
+    let guid = crate::custom_version_guid("FSequencerObjectVersion").unwrap();
+    assert_eq!(guid.to_string(), "7B5AE74C-D2704C10-A9585798-0B212A5A");
+    assert_eq!(
+      crate::custom_version_name(&guid),
+      Some("FSequencerObjectVersion")
+    );
+  }
+
+  #[test]
+  fn it_widens_math_structs_with_large_world_coordinates() {
+    let mut metadata = save_1_metadata();
+    assert!(!metadata.archive_version().large_world_coordinates);
+    metadata.engine_major = 5;
+    assert!(metadata.archive_version().large_world_coordinates);
+
+    let location = Vector {
+      x: 1e9 + 0.25,
+      y: -2.0,
+      z: 3.5,
+    };
+    let parsed = assert_round_trips_with(
+      metadata,
+      vec![
+        (
+          "Location",
+          Property::Struct(StructPropertyValue::Vector(location)),
+        ),
+        (
+          "Tint",
+          Property::Struct(StructPropertyValue::LinearColor(LinearColor {
+            r: 1.0,
+            g: 0.5,
+            b: 0.25,
+            a: 1.0,
+          })),
+        ),
+      ],
+    );
+
+    let location_property = parsed.properties.get("Location").unwrap();
+    assert_eq!(location_property.tag.size, 24);
+    assert!(matches!(
+      location_property.value,
+      Property::Struct(StructPropertyValue::Vector(v)) if v == location
+    ));
+    // Colors are not affected by large world coordinates
+    assert_eq!(parsed.properties.get("Tint").unwrap().tag.size, 16);
+  }
+
+  #[test]
+  fn it_round_trips_arrays_of_every_element_type() {
+    let array = |element_type: &str, values| {
+      Property::Array(ArrayProperty {
+        element_type: element_type.to_owned(),
+        struct_tag: None,
+        values: ArrayPropertyValues::Elements(values),
+      })
+    };
+
+    assert_round_trips(vec![
+      (
+        "Bools",
+        array(
+          "BoolProperty",
+          vec![
+            ArrayPropertyValue::Bool(true),
+            ArrayPropertyValue::Bool(false),
+          ],
+        ),
+      ),
+      (
+        "Strs",
+        array(
+          "StrProperty",
+          vec![
+            ArrayPropertyValue::Str("Rock".to_owned()),
+            ArrayPropertyValue::Str("".to_owned()),
+            ArrayPropertyValue::Str("Stöne".to_owned()),
+          ],
+        ),
+      ),
+      (
+        "Names",
+        array(
+          "NameProperty",
+          vec![ArrayPropertyValue::Name("Hoxxes".to_owned())],
+        ),
+      ),
+      (
+        "Enums",
+        array(
+          "EnumProperty",
+          vec![ArrayPropertyValue::Enum("EBiome::MagmaCore".to_owned())],
+        ),
+      ),
+      (
+        "Texts",
+        array(
+          "TextProperty",
+          vec![ArrayPropertyValue::Text(FText {
+            flags: 0,
+            history: TextHistory::None {
+              culture_invariant_string: Some("Mushroom".to_owned()),
+            },
+          })],
+        ),
+      ),
+      (
+        "UInt32s",
+        array("UInt32Property", vec![ArrayPropertyValue::UInt32(u32::MAX)]),
+      ),
+      ("Empty", array("IntProperty", vec![])),
+    ]);
+  }
+
+  #[test]
+  fn it_round_trips_maps_of_any_key_and_value_type() {
+    let map = |key_type: &str, value_type: &str, entries| {
+      Property::Map(MapProperty {
+        key_type: key_type.to_owned(),
+        value_type: value_type.to_owned(),
+        keys_to_remove: vec![],
+        entries,
+      })
+    };
+
+    assert_round_trips(vec![
+      (
+        "StrToText",
+        map(
+          "StrProperty",
+          "TextProperty",
+          vec![(
+            MapPropertyKey::Str("Greeting".to_owned()),
+            MapPropertyValue::Text(FText {
+              flags: 0,
+              history: TextHistory::None {
+                culture_invariant_string: Some("Rock and Stone".to_owned()),
+              },
+            }),
+          )],
+        ),
+      ),
+      (
+        "NameToBool",
+        map(
+          "NameProperty",
+          "BoolProperty",
+          vec![
+            (
+              MapPropertyKey::Name("Driller".to_owned()),
+              MapPropertyValue::Bool(true),
+            ),
+            (
+              MapPropertyKey::Name("Scout".to_owned()),
+              MapPropertyValue::Bool(false),
+            ),
+          ],
+        ),
+      ),
+      (
+        "EnumToStruct",
+        map(
+          "EnumProperty",
+          "StructProperty",
+          vec![(
+            MapPropertyKey::Enum("EBiome::AzureWeald".to_owned()),
+            MapPropertyValue::Struct(StructPropertyValue::Untyped(
+              [("Visits".to_owned(), tagged(Property::Int(12)))]
+                .into_iter()
+                .collect(),
+            )),
+          )],
+        ),
+      ),
+      (
+        "ObjectToName",
+        map(
+          "ObjectProperty",
+          "NameProperty",
+          vec![(
+            MapPropertyKey::Object("/Game/Minerals/Gold.Gold".to_owned()),
+            MapPropertyValue::Name("Gold".to_owned()),
+          )],
+        ),
+      ),
+      (
+        "Overrides",
+        Property::Map(MapProperty {
+          key_type: "IntProperty".to_owned(),
+          value_type: "StrProperty".to_owned(),
+          keys_to_remove: vec![MapPropertyKey::Int(4), MapPropertyKey::Int(5)],
+          entries: vec![(
+            MapPropertyKey::Int(1),
+            MapPropertyValue::Str("one".to_owned()),
+          )],
+        }),
+      ),
+    ]);
+
+    // Bools in maps are checked like any other bool
+    let save_file = save_with(vec![(
+      "IntToBool",
+      map(
+        "IntProperty",
+        "BoolProperty",
+        vec![(MapPropertyKey::Int(7), MapPropertyValue::Bool(true))],
+      ),
+    )]);
+    let mut written = crate::write_save_data(&save_file);
+    let entry = written
+      .windows(5)
+      .position(|w| w == [7, 0, 0, 0, 1])
+      .unwrap();
+    written[entry + 4] = 2;
+    let error = crate::parse_save_data(&written).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::InvalidBool);
+    assert_eq!(error.property_path.unwrap().to_string(), "IntToBool[0]");
+  }
+
+  #[test]
+  fn it_round_trips_sets_of_any_element_type() {
+    let set = |element_type: &str, elements_to_remove, elements| {
+      Property::Set(SetProperty {
+        element_type: element_type.to_owned(),
+        elements_to_remove,
+        elements,
+      })
+    };
+
+    let parsed = assert_round_trips_with(
+      save_1_metadata(),
+      vec![
+        (
+          "Ints",
+          set(
+            "IntProperty",
+            vec![SetPropertyValue::Int(0)],
+            vec![SetPropertyValue::Int(1), SetPropertyValue::Int(2)],
+          ),
+        ),
+        (
+          "Names",
+          set(
+            "NameProperty",
+            vec![],
+            vec![SetPropertyValue::Name("Glyphid".to_owned())],
+          ),
+        ),
+        (
+          "Strs",
+          set(
+            "StrProperty",
+            vec![],
+            vec![SetPropertyValue::Str("Mactera".to_owned())],
+          ),
+        ),
+        (
+          "Objects",
+          set(
+            "ObjectProperty",
+            vec![],
+            vec![SetPropertyValue::Object(
+              "/Game/Enemies/Praetorian".to_owned(),
+            )],
+          ),
+        ),
+        (
+          "Enums",
+          set(
+            "EnumProperty",
+            vec![],
+            vec![SetPropertyValue::Enum("EMissionType::Salvage".to_owned())],
+          ),
+        ),
+        (
+          "Guids",
+          set(
+            "StructProperty",
+            vec![SetPropertyValue::Struct(StructPropertyValue::Guid(Guid([
+              9, 9, 9, 9,
+            ])))],
+            vec![SetPropertyValue::Struct(StructPropertyValue::Guid(Guid([
+              1, 2, 3, 4,
+            ])))],
+          ),
+        ),
+        (
+          "Structs",
+          set(
+            "StructProperty",
+            vec![],
+            vec![SetPropertyValue::Struct(StructPropertyValue::Untyped(
+              [("Depth".to_owned(), tagged(Property::Int(3)))]
+                .into_iter()
+                .collect(),
+            ))],
+          ),
+        ),
+      ],
+    );
+
+    let Property::Set(ints) = &parsed.properties.get("Ints").unwrap().value else {
+      panic!("Ints is not a set");
+    };
+    assert!(matches!(
+      ints.elements_to_remove[..],
+      [SetPropertyValue::Int(0)]
+    ));
+  }
+
+  #[test]
+  fn it_uses_struct_type_hints_for_maps_and_sets() {
+    let color = |r| {
+      StructPropertyValue::LinearColor(LinearColor {
+        r,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+      })
+    };
+    let save_file = save_with(vec![
+      (
+        "Paints",
+        Property::Map(MapProperty {
+          key_type: "StructProperty".to_owned(),
+          value_type: "StructProperty".to_owned(),
+          keys_to_remove: vec![],
+          entries: vec![(
+            MapPropertyKey::Struct(StructPropertyValue::Guid(Guid([1, 2, 3, 4]))),
+            MapPropertyValue::Struct(color(0.5)),
+          )],
+        }),
+      ),
+      (
+        "Favorites",
+        Property::Set(SetProperty {
+          element_type: "StructProperty".to_owned(),
+          elements_to_remove: vec![],
+          elements: vec![SetPropertyValue::Struct(color(0.25))],
+        }),
+      ),
+      (
+        "Nones",
+        Property::Set(SetProperty {
+          element_type: "StructProperty".to_owned(),
+          elements_to_remove: vec![],
+          elements: vec![SetPropertyValue::Struct(StructPropertyValue::Foreign {
+            name: "None".to_owned(),
+            value: [("Level".to_owned(), tagged(Property::Int(3)))]
+              .into_iter()
+              .collect(),
+          })],
+        }),
+      ),
+    ]);
+    let written = crate::write_save_data(&save_file);
+
+    assert!(crate::parse_save_data(&written).is_err());
+
+    let options = ParseOptions::new()
+      .with_struct_type("Paints.Key", "Guid")
+      .with_struct_type("Paints.Value", "LinearColor")
+      .with_struct_type("Favorites", "LinearColor")
+      .with_struct_type("Nones", "None");
+    let parsed = crate::parse_save_data_with(&written, &options).unwrap();
+    assert_eq!(
+      serde_json::to_string(&save_file).unwrap(),
+      serde_json::to_string(&parsed).unwrap()
+    );
+    assert_eq!(written, crate::write_save_data(&parsed));
+
+    // A struct type named "None" is a struct type like any other
+    let Property::Set(nones) = &parsed.properties.get("Nones").unwrap().value else {
+      panic!("Nones is not a set");
+    };
+    assert_eq!(nones.elements.len(), 1);
+    assert!(matches!(
+      &nones.elements[0],
+      SetPropertyValue::Struct(s) if s.struct_type() == Some("None")
+    ));
+  }
+
+  #[test]
+  fn it_serializes_maps_with_struct_keys_as_pairs() {
+    let struct_map = |key| {
+      Property::Map(MapProperty {
+        key_type: "StructProperty".to_owned(),
+        value_type: "IntProperty".to_owned(),
+        keys_to_remove: vec![],
+        entries: vec![(MapPropertyKey::Struct(key), MapPropertyValue::Int(7))],
+      })
+    };
+    let save_file = save_with(vec![
+      (
+        "Spots",
+        struct_map(StructPropertyValue::Vector(Vector {
+          x: 1.0,
+          y: 2.0,
+          z: 3.0,
+        })),
+      ),
+      (
+        "Stats",
+        struct_map(StructPropertyValue::Foreign {
+          name: "DwarfStats".to_owned(),
+          value: [("Level".to_owned(), tagged(Property::Int(3)))]
+            .into_iter()
+            .collect(),
+        }),
+      ),
+    ]);
+    let written = crate::write_save_data(&save_file);
+
+    let options = ParseOptions::new()
+      .with_struct_type("Spots.Key", "Vector")
+      .with_struct_type("Stats.Key", "DwarfStats");
+    let parsed = crate::parse_save_data_with(&written, &options).unwrap();
+    let json = serde_json::to_string(&parsed.properties).unwrap();
+    assert!(json.contains("\"Spots\":[[{\"x\":1.0,\"y\":2.0,\"z\":3.0},7]]"));
+    assert!(json.contains("\"Stats\":[[{\"DwarfStats\":{\"Level\":3}},7]]"));
+    assert_eq!(written, crate::write_save_data(&parsed));
+  }
+
+  #[test]
+  fn it_keeps_unknown_properties_raw_in_lenient_mode() {
+    // The tag of an array holds its element type, as a length prefixed and
+    // null terminated string
+    let mut element_type = 14i32.to_le_bytes().to_vec();
+    element_type.extend_from_slice(b"FancyProperty\0");
+    let save_file = save_with(vec![
+      (
+        "Fancy",
+        Property::Unknown {
+          type_name: "FancyProperty".to_owned(),
+          tag_data: vec![],
+          raw: vec![1, 2, 3],
+        },
+      ),
+      (
+        "Fancies",
+        Property::Unknown {
+          type_name: "ArrayProperty".to_owned(),
+          tag_data: element_type,
+          raw: vec![1, 0, 0, 0, 4, 5],
+        },
+      ),
+      ("Level", Property::Int(7)),
+    ]);
+    let written = crate::write_save_data(&save_file);
+
+    let error = crate::parse_save_data(&written).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::UnknownPropertyType);
+    assert_eq!(error.property_path.unwrap().to_string(), "Fancy");
+
+    let options = ParseOptions::new().with_mode(ParseMode::Lenient);
+    let parsed = crate::parse_save_data_with(&written, &options).unwrap();
+    assert_eq!(
+      serde_json::to_string(&save_file.properties).unwrap(),
+      serde_json::to_string(&parsed.properties).unwrap()
+    );
+    assert_eq!(
+      parsed
+        .warnings
+        .iter()
+        .map(|w| w.path.to_string())
+        .collect::<Vec<_>>(),
+      ["Fancy", "Fancies"]
+    );
+    assert_eq!(written, crate::write_save_data(&parsed));
+  }
+
+  #[test]
+  fn it_applies_parse_options() {
+    let save_file = save_with(vec![
+      ("Level", Property::Int(7)),
+      ("Name", Property::Str("Karl".to_owned())),
+    ]);
+    let written = crate::write_save_data(&save_file);
+    let lenient = || ParseOptions::new().with_mode(ParseMode::Lenient);
+
+    // A string longer than the maximum
+    let options = ParseOptions::new().with_max_string_length(4);
+    let error = crate::parse_save_data_with(&written, &options).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::InvalidStringLength);
+    assert!(error.message.contains("over the maximum of 4"));
+
+    // The maximum includes the null-terminator, and strings of exactly that
+    // length are accepted
+    let long_name = "a".repeat(299);
+    let long = crate::write_save_data(&save_with(vec![("Name", Property::Str(long_name))]));
+    let options = ParseOptions::new().with_max_string_length(300);
+    assert!(crate::parse_save_data_with(&long, &options).is_ok());
+    let options = ParseOptions::new().with_max_string_length(299);
+    let error = crate::parse_save_data_with(&long, &options).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::InvalidStringLength);
+
+    // The longest length there is, as a wide string, is rejected however high
+    // the maximum is
+    let karl = [&5i32.to_le_bytes()[..], b"Karl\0"].concat();
+    let karl_offset = written.windows(karl.len()).position(|w| w == karl).unwrap();
+    let mut min_length = written.clone();
+    min_length[karl_offset..karl_offset + 4].copy_from_slice(&i32::MIN.to_le_bytes());
+    let options = ParseOptions::new().with_max_string_length(u32::MAX);
+    let error = crate::parse_save_data_with(&min_length, &options).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::InvalidStringLength);
+
+    // Garbage after the save
+    let mut trailing = written.clone();
+    trailing.extend_from_slice(&[1, 2, 3]);
+    assert!(crate::parse_save_data(&trailing).is_err());
+    let options = ParseOptions::new().with_allow_trailing_bytes(true);
+    assert!(crate::parse_save_data_with(&trailing, &options).is_ok());
+    let parsed = crate::parse_save_data_with(&trailing, &lenient()).unwrap();
+    assert_eq!(parsed.warnings[0].offset, written.len() - 4);
+    assert_eq!(parsed.trailer, [0, 0, 0, 0, 1, 2, 3]);
+
+    let (oversized, size_offset) = oversize_int(&written, "Level");
+    assert!(crate::parse_save_data(&oversized).is_err());
+    let options = lenient().with_verify_sizes(true);
+    let parsed = crate::parse_save_data_with(&oversized, &options).unwrap();
+    assert!(matches!(
+      &parsed.properties.get("Level").unwrap().value,
+      Property::Unknown { raw, .. } if raw == &[7, 0, 0, 0, 0]
+    ));
+    assert_eq!(parsed.warnings[0].path.to_string(), "Level");
+    assert_eq!(parsed.warnings[0].offset, size_offset + 4 + 4 + 1);
+    assert!(matches!(
+      &parsed.properties.get("Name").unwrap().value,
+      Property::Str(name) if name == "Karl"
+    ));
+  }
+
+  #[test]
+  fn it_reports_size_mismatches() {
+    let save_file = save_with(vec![(
+      "Stats",
+      Property::Struct(StructPropertyValue::Foreign {
+        name: "DwarfStats".to_owned(),
+        value: [("Level".to_owned(), tagged(Property::Int(7)))]
+          .into_iter()
+          .collect(),
+      }),
+    )]);
+    let (oversized, _) = oversize_int(&crate::write_save_data(&save_file), "Level");
+
+    let error = crate::parse_save_data(&oversized).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::SizeMismatch);
+    let error = error.to_string();
+    assert!(
+      error.contains("Stats.Level declared a size of 5 bytes but 4 were read"),
+      "{}",
+      error
+    );
+  }
+
+  #[test]
+  fn it_keeps_the_data_after_the_root_property_map() {
+    for (save_file_name, save_file_data) in read_save_files() {
+      let save_file = crate::parse_save_data(&save_file_data).unwrap();
+      assert_eq!(save_file.trailer, [0, 0, 0, 0], "{}", save_file_name);
+    }
+
+    let save_file_data = fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/test/save_1.sav")).unwrap();
+
+    let unpadded = &save_file_data[..save_file_data.len() - 4];
+    let save_file = crate::parse_save_data(unpadded).unwrap();
+    assert!(save_file.trailer.is_empty());
+    assert_eq!(crate::write_save_data(&save_file), unpadded);
+
+    let concatenated = [&save_file_data[..], &save_file_data[..]].concat();
+    assert!(crate::parse_save_data(&concatenated).is_err());
+    let options = ParseOptions::new().with_allow_trailing_bytes(true);
+    let save_file = crate::parse_save_data_with(&concatenated, &options).unwrap();
+    assert_eq!(save_file.trailer, &concatenated[save_file_data.len() - 4..]);
+    assert_eq!(crate::write_save_data(&save_file), concatenated);
+  }
+
+  #[test]
+  fn it_locates_errors_in_truncated_saves() {
+    let save_file_data = fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/test/save_1.sav")).unwrap();
+    for len in [3, 10, save_file_data.len() / 2, save_file_data.len() - 5] {
+      let truncated = &save_file_data[..len];
+      let error = crate::parse_save_data(truncated).unwrap_err();
+      assert!(error.offset <= len);
+      assert!(!error.to_string().is_empty());
+    }
+
+    // The final "None" is missing its null terminator
+    let error = crate::parse_save_data(&save_file_data[..save_file_data.len() - 5]).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::UnexpectedEof);
+    assert_eq!(error.offset, save_file_data.len() - 5);
+    assert!(error.hex_context.ends_with("05 00 00 00 4E 6F 6E 65 []"));
+  }
+
+  #[test]
+  fn it_locates_errors_by_property_path() {
+    let stats = |level| {
+      ArrayPropertyValue::Struct(StructPropertyValue::Foreign {
+        name: "DwarfStats".to_owned(),
+        value: [("Level".to_owned(), tagged(Property::Int(level)))]
+          .into_iter()
+          .collect(),
+      })
+    };
+    let save_file = save_with(vec![(
+      "Dwarves",
+      Property::Array(ArrayProperty {
+        element_type: "StructProperty".to_owned(),
+        struct_tag: Some(ArrayStructTag {
+          name: "Dwarves".to_owned(),
+          struct_type: "DwarfStats".to_owned(),
+          tag: PropertyTag::default(),
+        }),
+        values: ArrayPropertyValues::Elements(vec![stats(1), stats(2)]),
+      }),
+    )]);
+    let written = crate::write_save_data(&save_file);
+    let first_level = written.windows(6).position(|w| w == b"Level\0").unwrap();
+    let (oversized, size_offset) = oversize_int(&written[first_level + 6..], "Level");
+    let oversized = [&written[..first_level + 6], &oversized[..]].concat();
+
+    let error = crate::parse_save_data(&oversized).unwrap_err();
+    let property_path = error.property_path.unwrap();
+    assert_eq!(
+      property_path.segments(),
+      [
+        PathSegment::Name("Dwarves".to_owned()),
+        PathSegment::Index(1),
+        PathSegment::Name("Level".to_owned())
+      ]
+    );
+    // The form of the path hints are given for
+    assert_eq!(property_path.names(), "Dwarves.Level");
+    assert!(error.trace.is_empty());
+    assert_eq!(error.offset, first_level + 6 + size_offset + 4 + 4 + 1);
+    assert!(error.hex_context.contains("[02] 00 00 00 00"));
+  }
+
+  #[test]
+  fn it_returns_errors_which_outlive_the_save() {
+    let error: Box<dyn std::error::Error + Send + Sync> = {
+      let not_a_save = b"SAVE0000".to_vec();
+      let error = crate::parse_save_data(&not_a_save).unwrap_err();
+      assert_eq!(error.kind, ParseErrorKind::InvalidMagic);
+      assert_eq!(error.offset, 0);
+      Box::new(error)
+    };
+    assert!(error.to_string().contains("at offset 0"));
+  }
+
+  #[test]
+  fn it_traces_the_properties_it_parses() {
+    let save_file_data = fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/test/save_1.sav")).unwrap();
+    assert!(crate::parse_save_data(&save_file_data)
+      .unwrap()
+      .trace
+      .is_empty());
+
+    let options = ParseOptions::new().with_trace(true);
+    let save_file = crate::parse_save_data_with(&save_file_data, &options).unwrap();
+    let first = &save_file.trace[0];
+    let (name, property) = save_file.properties.iter().next().unwrap();
+    assert_eq!(first.path.to_string(), *name);
+    assert_eq!(first.property_type, property.value.type_name());
+    assert_eq!(first.size, property.tag.size);
+    assert!(save_file
+      .trace
+      .iter()
+      .any(|entry| entry.path.segments().contains(&PathSegment::Index(0))));
+
+    let truncated = &save_file_data[..save_file_data.len() / 2];
+    let error = crate::parse_save_data_with(truncated, &options).unwrap_err();
+    let last = error.trace.last().unwrap();
+    assert!(last.offset <= error.offset);
+    assert!(error
+      .property_path
+      .unwrap()
+      .to_string()
+      .starts_with(&last.path.to_string()));
+  }
+
+  #[test]
+  fn it_skips_root_properties_which_fail_to_parse() {
+    let save_file = save_with(vec![
+      ("Level", Property::Int(7)),
+      ("Name", Property::Str("Karl".to_owned())),
+    ]);
+    let (oversized, _) = oversize_int(&crate::write_save_data(&save_file), "Level");
+    assert!(crate::parse_save_data(&oversized).is_err());
+
+    let e = crate::parse_save_data_partial(&oversized, &ParseOptions::default()).unwrap_err();
+    let [error] = <[_; 1]>::try_from(e.errors).unwrap();
+    assert_eq!(error.kind, ParseErrorKind::SizeMismatch);
+    assert_eq!(error.property_path.unwrap().to_string(), "Level");
+    let partial = e.save.unwrap();
+    assert!(matches!(
+      &partial.properties.get("Level").unwrap().value,
+      Property::Unknown { raw, .. } if raw == &[7, 0, 0, 0, 0]
+    ));
+    assert!(matches!(
+      &partial.properties.get("Name").unwrap().value,
+      Property::Str(name) if name == "Karl"
+    ));
+    assert_eq!(crate::write_save_data(&partial), oversized);
+  }
+
+  #[test]
+  fn it_keeps_the_properties_parsed_before_a_failure() {
+    let save_file_data = fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/test/save_1.sav")).unwrap();
+    let save_file = crate::parse_save_data(&save_file_data).unwrap();
+    let truncated = &save_file_data[..save_file_data.len() / 2];
+    assert!(crate::parse_save_data(truncated).is_err());
+
+    let options = ParseOptions::default();
+    let e = crate::parse_save_data_partial(truncated, &options).unwrap_err();
+    let partial = e.save.unwrap();
+    let [error] = <[_; 1]>::try_from(e.errors).unwrap();
+    assert_eq!(error.kind, ParseErrorKind::UnexpectedEof);
+    let failed = match &error.property_path.unwrap().segments()[0] {
+      PathSegment::Name(name) => name.clone(),
+      PathSegment::Index(_) => unreachable!(),
+    };
+
+    let names: Vec<&String> = save_file.properties.iter().map(|(name, _)| name).collect();
+    let parsed: Vec<&String> = partial.properties.iter().map(|(name, _)| name).collect();
+    assert!(!parsed.is_empty());
+    assert_eq!(parsed, names[..parsed.len()]);
+    assert_eq!(*names[parsed.len()], failed);
+    assert_eq!(
+      partial.metadata.save_version,
+      save_file.metadata.save_version
+    );
+
+    // Saves which fail before the properties have nothing to keep
+    let e = crate::parse_save_data_partial(&truncated[..10], &options).unwrap_err();
+    assert!(e.save.is_none());
+    assert_eq!(e.errors.len(), 1);
+  }
 }
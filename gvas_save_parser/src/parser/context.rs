@@ -0,0 +1,210 @@
+use super::{
+  path::join_names, ArchiveVersion, ParseOptions, ParseTraceEntry, ParseWarning, PathSegment,
+  PropertyPath,
+};
+use nom::Err;
+use std::{cell::RefCell, rc::Rc};
+
+/**
+ * What is found out over the course of a parse, shared by every copy of the
+ * context so that it can be read once the parse is over, whether or not it
+ * succeeded.
+ */
+#[derive(Debug, Default)]
+pub struct ParseState {
+  pub warnings: Vec<ParseWarning>,
+  /**
+   * The path of the innermost property which failed to parse.
+   */
+  pub failure_path: Option<PropertyPath>,
+  /**
+   * Every property parsed so far, if tracing is on.
+   */
+  pub trace: Vec<ParseTraceEntry>,
+}
+
+/**
+ * The last segment of a path and the path before it. Nested contexts share
+ * the path they extend rather than copying it, since the whole path is only
+ * needed when something about the property is recorded.
+ */
+#[derive(Debug)]
+struct PathNode {
+  parent: Option<Rc<PathNode>>,
+  segment: PathSegment,
+}
+
+/**
+ * Everything the property parsers need to know besides the bytes in front of
+ * them. Each nested parser is given its own copy, with the path extended by
+ * the name of the property or the index of the element it is parsing, while
+ * the state is shared by every copy.
+ */
+#[derive(Debug, Clone)]
+pub struct ParseContext {
+  pub version: ArchiveVersion,
+  pub options: Rc<ParseOptions>,
+  path: Option<Rc<PathNode>>,
+  /**
+   * The length of the whole save, so that offsets can be worked out from the
+   * length of the input which is left.
+   */
+  pub input_len: usize,
+  pub state: Rc<RefCell<ParseState>>,
+}
+
+impl ParseContext {
+  pub fn new(
+    version: ArchiveVersion,
+    options: Rc<ParseOptions>,
+    input_len: usize,
+    state: Rc<RefCell<ParseState>>,
+  ) -> Self {
+    Self {
+      version,
+      options,
+      path: None,
+      input_len,
+      state,
+    }
+  }
+
+  pub fn with_segment(&self, segment: &str) -> Self {
+    self.with(PathSegment::Name(segment.to_owned()))
+  }
+
+  pub fn with_index(&self, index: usize) -> Self {
+    self.with(PathSegment::Index(index))
+  }
+
+  fn with(&self, segment: PathSegment) -> Self {
+    Self {
+      version: self.version,
+      options: self.options.clone(),
+      path: Some(Rc::new(PathNode {
+        parent: self.path.clone(),
+        segment,
+      })),
+      input_len: self.input_len,
+      state: self.state.clone(),
+    }
+  }
+
+  /**
+   * The path of the current property, with the index of each array element,
+   * set element and map entry along the way.
+   */
+  pub fn path(&self) -> PropertyPath {
+    let mut path = PropertyPath::new();
+    for segment in self.segments().into_iter().rev() {
+      path.push(segment.clone());
+    }
+    path
+  }
+
+  /**
+   * The segments of the current path from the last one back.
+   */
+  fn segments(&self) -> Vec<&PathSegment> {
+    let mut segments = vec![];
+    let mut node = self.path.as_deref();
+    while let Some(PathNode { parent, segment }) = node {
+      segments.push(segment);
+      node = parent.as_deref();
+    }
+    segments
+  }
+
+  /**
+   * The names along the current path without any indices, which is the form
+   * of the paths types are given for in `ParseOptions`.
+   */
+  fn names(&self) -> String {
+    join_names(self.segments().into_iter().rev())
+  }
+
+  /**
+   * The struct type given for the current path in `ParseOptions`, if any.
+   */
+  pub fn struct_type(&self) -> Option<&str> {
+    if self.options.struct_types.is_empty() {
+      return None;
+    }
+    self
+      .options
+      .struct_types
+      .get(&self.names())
+      .map(String::as_str)
+  }
+
+  /**
+   * The enum type given for the current path in `ParseOptions`, if any.
+   */
+  pub fn enum_type(&self) -> Option<&str> {
+    if self.options.enum_types.is_empty() {
+      return None;
+    }
+    self
+      .options
+      .enum_types
+      .get(&self.names())
+      .map(String::as_str)
+  }
+
+  /**
+   * The offset of the given input from the start of the save.
+   */
+  pub fn offset(&self, input: &[u8]) -> usize {
+    self.input_len - input.len()
+  }
+
+  pub fn warn(&self, input: &[u8], message: String) {
+    self.state.borrow_mut().warnings.push(ParseWarning {
+      offset: self.offset(input),
+      path: self.path(),
+      message,
+    });
+  }
+
+  /**
+   * Records that the current property was found, if tracing is on.
+   */
+  pub fn trace(&self, input: &[u8], property_type: &str, size: u32) {
+    if self.options.trace {
+      self.state.borrow_mut().trace.push(ParseTraceEntry {
+        offset: self.offset(input),
+        path: self.path(),
+        property_type: property_type.to_owned(),
+        size,
+      });
+    }
+  }
+
+  /**
+   * Records the current path as the one which failed to parse, unless a
+   * property inside of it already has been, and passes the error on.
+   */
+  pub fn fail<E>(&self, e: Err<E>) -> Err<E> {
+    let mut state = self.state.borrow_mut();
+    if state.failure_path.is_none() && self.path.is_some() {
+      state.failure_path = Some(self.path());
+    }
+    e
+  }
+
+  /**
+   * Forgets the recorded failure, once it has been recovered from.
+   */
+  pub fn recover(&self) {
+    self.state.borrow_mut().failure_path = None;
+  }
+
+  /**
+   * Takes the recorded failure, for a property which is being skipped past,
+   * along with the number of properties traced before it failed.
+   */
+  pub fn take_failure(&self) -> (Option<PropertyPath>, usize) {
+    let mut state = self.state.borrow_mut();
+    (state.failure_path.take(), state.trace.len())
+  }
+}
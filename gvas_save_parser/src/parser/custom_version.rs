@@ -0,0 +1,130 @@
+use super::primitives::Guid;
+
+/**
+ * The GUIDs of the custom versions engine code registers, by the name of the
+ * struct they are declared in. Saves record the version of every custom
+ * version the engine knows about, including ones from plugins and game code
+ * which are not listed here.
+ */
+pub const KNOWN_CUSTOM_VERSIONS: &[(&str, Guid)] = &[
+  (
+    "FAnimObjectVersion",
+    Guid([0xAF43A65D, 0x7FD34947, 0x98733E8E, 0xD9C1BB05]),
+  ),
+  (
+    "FAnimPhysObjectVersion",
+    Guid([0x29E575DD, 0xE0A34627, 0x9D10D276, 0x232CDCEA]),
+  ),
+  (
+    "FBlueprintsObjectVersion",
+    Guid([0xB0D832E4, 0x1F894F0D, 0xACCF7EB7, 0x36FD4AA2]),
+  ),
+  (
+    "FBuildObjectVersion",
+    Guid([0xE1C64328, 0xA22C4D53, 0xA36C8E86, 0x6417BD8C]),
+  ),
+  (
+    "FCoreObjectVersion",
+    Guid([0x375EC13C, 0x06E448FB, 0xB50084F0, 0x262A717E]),
+  ),
+  (
+    "FDestructionObjectVersion",
+    Guid([0x174F1F0B, 0xB4C645A5, 0xB13F2EE8, 0xD0FB917D]),
+  ),
+  (
+    "FEditorObjectVersion",
+    Guid([0xE4B068ED, 0xF49442E9, 0xA231DA0B, 0x2E46BB41]),
+  ),
+  (
+    "FEnterpriseObjectVersion",
+    Guid([0x9DFFBCD6, 0x494F0158, 0xE2211282, 0x3C92A888]),
+  ),
+  (
+    "FFortniteMainBranchObjectVersion",
+    Guid([0x601D1886, 0xAC644F84, 0xAA16D3DE, 0x0DEAC7D6]),
+  ),
+  (
+    "FFortniteReleaseBranchCustomObjectVersion",
+    Guid([0xE7086368, 0x6B234C58, 0x84391B70, 0x16265E91]),
+  ),
+  (
+    "FFrameworkObjectVersion",
+    Guid([0xCFFC743F, 0x43B04480, 0x939114DF, 0x171D2073]),
+  ),
+  (
+    "FGeometryObjectVersion",
+    Guid([0x6EACA3D4, 0x40EC4CC1, 0xB7868BED, 0x09428FC5]),
+  ),
+  (
+    "FLoadTimesObjectVersion",
+    Guid([0xC2A15278, 0xBFE74AFE, 0x6C1790FF, 0x531DF755]),
+  ),
+  (
+    "FMobileObjectVersion",
+    Guid([0xB02B49B5, 0xBB2044E9, 0xA30432B7, 0x52E40360]),
+  ),
+  (
+    "FNiagaraCustomVersion",
+    Guid([0xFCF57AFA, 0x50764283, 0xB9A9E658, 0xFFA02D32]),
+  ),
+  (
+    "FNiagaraObjectVersion",
+    Guid([0xF2AED0AC, 0x9AFE416F, 0x8664AA7F, 0xFA26D6FC]),
+  ),
+  (
+    "FOnlineObjectVersion",
+    Guid([0x6631380F, 0x2D4D43E0, 0x8009CF27, 0x6956A95A]),
+  ),
+  (
+    "FPhysicsObjectVersion",
+    Guid([0x78F01B33, 0xEBEA4F98, 0xB9B484EA, 0xCCB95AA2]),
+  ),
+  (
+    "FReleaseObjectVersion",
+    Guid([0x9C54D522, 0xA8264FBE, 0x94210746, 0x61B482D0]),
+  ),
+  (
+    "FRenderingObjectVersion",
+    Guid([0x12F88B9F, 0x88754AFC, 0xA67CD90C, 0x383ABD29]),
+  ),
+  (
+    "FSequencerObjectVersion",
+    Guid([0x7B5AE74C, 0xD2704C10, 0xA9585798, 0x0B212A5A]),
+  ),
+  (
+    "FUE5MainStreamObjectVersion",
+    Guid([0x697DD581, 0xE64F41AB, 0xAA4A51EC, 0xBEB7B628]),
+  ),
+  (
+    "FUE5PrivateFrostyStreamObjectVersion",
+    Guid([0x59DA5D52, 0x12324948, 0xB8785978, 0x70B8E98B]),
+  ),
+  (
+    "FUE5ReleaseStreamObjectVersion",
+    Guid([0xD89B5E42, 0x24BD4D46, 0x8412ACA8, 0xDF641779]),
+  ),
+  (
+    "FVRObjectVersion",
+    Guid([0xD7296918, 0x1DD64BDD, 0x9DE264A8, 0x3CC13884]),
+  ),
+];
+
+/**
+ * The name of the custom version with the given GUID, if it is a known one.
+ */
+pub fn custom_version_name(guid: &Guid) -> Option<&'static str> {
+  KNOWN_CUSTOM_VERSIONS
+    .iter()
+    .find(|(_, known)| known == guid)
+    .map(|(name, _)| *name)
+}
+
+/**
+ * The GUID of the known custom version with the given name.
+ */
+pub fn custom_version_guid(name: &str) -> Option<Guid> {
+  KNOWN_CUSTOM_VERSIONS
+    .iter()
+    .find(|(known, _)| *known == name)
+    .map(|(_, guid)| *guid)
+}
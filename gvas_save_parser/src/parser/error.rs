@@ -1,72 +1,253 @@
-use std::fmt::Display;
+use std::{
+  fmt::{Display, Write},
+  string::{FromUtf16Error, FromUtf8Error},
+};
 
+use super::{ParseTraceEntry, PropertyPath, SaveFile};
 use nom::error::{ContextError, ErrorKind, FromExternalError, ParseError};
+use serde::Serialize;
 
+/**
+ * The number of bytes either side of the error to include in the hex dump.
+ */
+const HEX_CONTEXT_LENGTH: usize = 16;
+
+/**
+ * What kind of problem stopped a save from being parsed.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ParseErrorKind {
+  /**
+   * The save ended in the middle of something, usually because the file is
+   * truncated.
+   */
+  UnexpectedEof,
+  /**
+   * The file does not start with "GVAS", so it is not a save.
+   */
+  InvalidMagic,
+  UnknownPropertyType,
+  UnknownTextHistoryType,
+  UnknownTextArgumentType,
+  InvalidUtf8,
+  InvalidUtf16,
+  InvalidBool,
+  /**
+   * A string is longer than the maximum string length, which usually means
+   * the parser has lost its place.
+   */
+  InvalidStringLength,
+  /**
+   * A property value did not take up the size declared in its tag.
+   */
+  SizeMismatch,
+  /**
+   * There is unexpected data after the root property map.
+   */
+  TrailingData,
+  Other,
+}
+
+/**
+ * An error found by the parsers in this crate, rather than by nom, for
+ * passing to `FromExternalError`.
+ */
 #[derive(Debug)]
-enum ParseErrorKind {
-  Parser(ErrorKind),
-  #[allow(dead_code)]
-  External { parser: ErrorKind, message: String },
+pub struct ParseFailure {
+  pub kind: ParseErrorKind,
+  pub message: String,
+}
+
+impl ParseFailure {
+  pub fn new(kind: ParseErrorKind, message: String) -> Self {
+    ParseFailure { kind, message }
+  }
 }
 
+impl Display for ParseFailure {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+/**
+ * The error the parsers build up as they fail, which points into the input
+ * and so is turned into a `SaveFileParseError` before it is returned.
+ */
 #[derive(Debug)]
-pub struct SaveFileParseError<'a> {
+pub struct InputError<'a> {
   input: &'a [u8],
+  kind: ParseErrorKind,
+  message: String,
   context: Vec<&'static str>,
-  error_stack: Vec<ParseErrorKind>,
 }
 
-impl<'a> ParseError<&'a [u8]> for SaveFileParseError<'a> {
-  fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
-    SaveFileParseError {
+impl<'a> InputError<'a> {
+  fn new(input: &'a [u8], kind: ParseErrorKind, message: String) -> Self {
+    InputError {
       input,
+      kind,
+      message,
       context: vec![],
-      error_stack: vec![ParseErrorKind::Parser(kind)],
     }
   }
 
-  fn append(_input: &[u8], kind: ErrorKind, mut other: Self) -> Self {
-    if !other.context.is_empty() {
-      other.error_stack.push(ParseErrorKind::Parser(kind))
+  /**
+   * Places the error within the whole save it was found in, along with the
+   * path of the property which failed to parse, if it was in one, and the
+   * trace of the parse up to the error.
+   */
+  pub fn into_owned(
+    self,
+    buffer: &[u8],
+    property_path: Option<PropertyPath>,
+    trace: Vec<ParseTraceEntry>,
+  ) -> SaveFileParseError {
+    let offset = buffer.len().saturating_sub(self.input.len());
+    SaveFileParseError {
+      kind: self.kind,
+      message: self.message,
+      offset,
+      property_path,
+      context: self.context.into_iter().rev().collect(),
+      hex_context: hex_context(buffer, offset),
+      trace,
     }
+  }
+}
+
+impl<'a> ParseError<&'a [u8]> for InputError<'a> {
+  fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+    let error_kind = match kind {
+      ErrorKind::Eof => ParseErrorKind::UnexpectedEof,
+      ErrorKind::Tag => ParseErrorKind::InvalidMagic,
+      _ => ParseErrorKind::Other,
+    };
+    InputError::new(input, error_kind, kind.description().to_owned())
+  }
+
+  fn append(_input: &[u8], _kind: ErrorKind, other: Self) -> Self {
     other
   }
 }
 
-impl<'a> ContextError<&'a [u8]> for SaveFileParseError<'a> {
+impl<'a> ContextError<&'a [u8]> for InputError<'a> {
   fn add_context(_input: &[u8], ctx: &'static str, mut other: Self) -> Self {
     other.context.push(ctx);
     other
   }
 }
 
-impl<'a, T: Display> FromExternalError<&'a [u8], T> for SaveFileParseError<'a> {
-  fn from_external_error(input: &'a [u8], kind: ErrorKind, e: T) -> Self {
-    SaveFileParseError {
-      input,
-      context: vec![],
-      error_stack: vec![ParseErrorKind::External {
-        parser: kind,
-        message: format!("{}", e),
-      }],
+impl<'a> FromExternalError<&'a [u8], ParseFailure> for InputError<'a> {
+  fn from_external_error(input: &'a [u8], _kind: ErrorKind, e: ParseFailure) -> Self {
+    InputError::new(input, e.kind, e.message)
+  }
+}
+
+impl<'a> FromExternalError<&'a [u8], FromUtf8Error> for InputError<'a> {
+  fn from_external_error(input: &'a [u8], _kind: ErrorKind, e: FromUtf8Error) -> Self {
+    InputError::new(input, ParseErrorKind::InvalidUtf8, e.to_string())
+  }
+}
+
+impl<'a> FromExternalError<&'a [u8], FromUtf16Error> for InputError<'a> {
+  fn from_external_error(input: &'a [u8], _kind: ErrorKind, e: FromUtf16Error) -> Self {
+    InputError::new(input, ParseErrorKind::InvalidUtf16, e.to_string())
+  }
+}
+
+/**
+ * A hex dump of the bytes around the offset, with the byte at the offset in
+ * brackets. Offsets at the end of the buffer are marked with empty brackets
+ * at the end of the dump.
+ */
+fn hex_context(buffer: &[u8], offset: usize) -> String {
+  let start = offset.saturating_sub(HEX_CONTEXT_LENGTH).min(buffer.len());
+  let end = (offset + HEX_CONTEXT_LENGTH).min(buffer.len());
+
+  let mut hex = format!("{:08X}:", start);
+  for (i, byte) in buffer[start..end].iter().enumerate() {
+    if start + i == offset {
+      write!(hex, " [{:02X}]", byte).unwrap();
+    } else {
+      write!(hex, " {:02X}", byte).unwrap();
     }
   }
+  if offset >= buffer.len() {
+    hex.push_str(" []");
+  }
+  hex
+}
+
+/**
+ * Why and where a save failed to parse. This owns everything it needs, so it
+ * can outlive the save it was parsed from.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SaveFileParseError {
+  pub kind: ParseErrorKind,
+  pub message: String,
+  /**
+   * The offset of the error from the start of the save.
+   */
+  pub offset: usize,
+  /**
+   * The path of the property which failed to parse, if the error was in one,
+   * e.g. `SchematicSave.ForceUnlocked[3]`.
+   */
+  pub property_path: Option<PropertyPath>,
+  /**
+   * What was being parsed when the error was found, from the outermost in.
+   */
+  pub context: Vec<&'static str>,
+  /**
+   * A hex dump of the bytes around the error, with the byte the error was
+   * found at in brackets.
+   */
+  pub hex_context: String,
+  /**
+   * Every property parsed before the error, if `ParseOptions::trace` is on.
+   */
+  pub trace: Vec<ParseTraceEntry>,
 }
 
-impl<'a> Display for SaveFileParseError<'a> {
+impl Display for SaveFileParseError {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} at offset {}", self.message, self.offset)?;
+    if let Some(property_path) = &self.property_path {
+      write!(f, " in {}", property_path)?;
+    }
     write!(
       f,
-      "Context\n\t{}\nError Stack\n\t{:?}\nNext 16 Bytes\n\t{}\n",
-      self
-        .context
-        .clone()
-        .into_iter()
-        .rev()
-        .collect::<Vec<_>>()
-        .join(" -> "),
-      self.error_stack,
-      String::from_utf8_lossy(&self.input[..16])
+      "\nContext\n\t{}\nBytes\n\t{}\n",
+      self.context.join(" -> "),
+      self.hex_context
     )
   }
 }
+
+impl std::error::Error for SaveFileParseError {}
+
+/**
+ * A save which failed to parse, along with what could be parsed of it. The
+ * save is missing if the failure was in the metadata. Otherwise it has every
+ * root property which could be parsed, with those which failed kept as
+ * unknown properties, but if a failure couldn't be skipped past, the
+ * properties after it are missing, so it should not be written back.
+ */
+#[derive(Debug)]
+pub struct PartialParseError {
+  pub save: Option<SaveFile>,
+  pub errors: Vec<SaveFileParseError>,
+}
+
+impl Display for PartialParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for error in &self.errors {
+      write!(f, "{}", error)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for PartialParseError {}
@@ -1,70 +1,147 @@
-use super::primitives::{parse_guid, parse_string, Guid};
+use super::{
+  custom_version::custom_version_guid,
+  error::ParseFailure,
+  primitives::{parse_guid, parse_string, Guid},
+};
 use nom::{
-  combinator::map,
+  combinator::cond,
   error::{context, ContextError, FromExternalError, ParseError},
   multi::length_count,
   number::complete::{le_u16 as u16, le_u32 as u32},
   sequence::tuple,
   IResult,
 };
-use serde::Serialize;
-use std::{
-  collections::HashMap,
-  string::{FromUtf16Error, FromUtf8Error},
-};
+use serde::{Serialize, Serializer};
+use std::string::{FromUtf16Error, FromUtf8Error};
+
+/**
+ * Save game version 2 added the custom versions to the header.
+ */
+const SAVE_VERSION_ADDED_CUSTOM_VERSIONS: u32 = 2;
+
+/**
+ * Save game version 3 added the UE5 package version, which is saved
+ * alongside the UE4 one.
+ */
+const SAVE_VERSION_PACKAGE_FILE_SUMMARY_VERSION_CHANGE: u32 = 3;
+
+/**
+ * The first UE5 package version in which math structs use doubles.
+ */
+const PACKAGE_VERSION_UE5_LARGE_WORLD_COORDINATES: u32 = 1004;
 
 #[derive(Debug, Serialize)]
 pub struct Metadata {
   pub save_version: u32,
   pub package_version: u32,
+  /**
+   * Only present from save game version 3.
+   */
+  pub package_version_ue5: Option<u32>,
   pub engine_major: u16,
   pub engine_minor: u16,
   pub engine_patch: u16,
   pub engine_build: u32,
   pub engine_build_id: String,
-  pub custom_format_version: u32,
-  pub custom_format_data: HashMap<Guid, u32>,
+  /**
+   * Only present from save game version 2, as are the custom versions in the
+   * custom format data, which is empty before then.
+   */
+  pub custom_format_version: Option<u32>,
+  /**
+   * The version of each custom version, in the order they are saved in. This
+   * is serialized as a map from GUID to version.
+   */
+  #[serde(serialize_with = "serialize_custom_format_data")]
+  pub custom_format_data: Vec<(Guid, u32)>,
   pub save_game_type: String,
 }
 
+/**
+ * The parts of the engine version which change how property values are laid
+ * out, worked out from the metadata before any properties are read.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveVersion {
+  /**
+   * UE5 widened the components of vectors, rotators and the like from f32 to
+   * f64 in order to support large worlds.
+   */
+  pub large_world_coordinates: bool,
+}
+
+impl Metadata {
+  /**
+   * UE5 saves record the UE5 package version, which says whether large world
+   * coordinates are in use. Older saves only have a UE4 package version, even
+   * if they were written by UE5, so the engine version decides it for them.
+   */
+  pub fn archive_version(&self) -> ArchiveVersion {
+    ArchiveVersion {
+      large_world_coordinates: match self.package_version_ue5 {
+        Some(version) => version >= PACKAGE_VERSION_UE5_LARGE_WORLD_COORDINATES,
+        None => self.engine_major >= 5,
+      },
+    }
+  }
+
+  /**
+   * The version of the known custom version with the given name, such as
+   * `FReleaseObjectVersion`, if the save records one.
+   */
+  pub fn custom_version(&self, name: &str) -> Option<u32> {
+    let guid = custom_version_guid(name)?;
+    self
+      .custom_format_data
+      .iter()
+      .find(|(g, _)| *g == guid)
+      .map(|(_, version)| *version)
+  }
+}
+
+/**
+ * The metadata is laid out in the structure:
+ *   [u32 | save version][u32 | package version][u32 | UE5 package version]
+ *   [u16 | major][u16 | minor][u16 | patch][u32 | build][string | build id]
+ *   [u32 | custom format version][custom format data][string | save game type]
+ * where the UE5 package version is only present from save game version 3,
+ * and the custom format version and data from save game version 2.
+ */
 pub fn parse_metadata<
   'a,
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
-    + FromExternalError<&'a [u8], FromUtf8Error>,
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&'a [u8], Metadata, E> {
-  context(
-    "metadata",
-    map(
-      tuple((
-        u32,
-        u32,
-        u16,
-        u16,
-        u16,
-        u32,
-        parse_string,
-        u32,
-        parse_custom_format_data,
-        parse_string,
-      )),
-      |(
-        save_version,
-        package_version,
-        engine_major,
-        engine_minor,
-        engine_patch,
-        engine_build,
-        engine_build_id,
-        custom_format_version,
-        custom_format_data,
-        save_game_type,
-      )| Metadata {
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Metadata, E> {
+  let string = parse_string(max_string_length);
+  context("metadata", move |input| {
+    let (input, (save_version, package_version)) = tuple((u32, u32))(input)?;
+    let (input, package_version_ue5) = cond(
+      save_version >= SAVE_VERSION_PACKAGE_FILE_SUMMARY_VERSION_CHANGE,
+      u32,
+    )(input)?;
+    let (input, (engine_major, engine_minor, engine_patch, engine_build, engine_build_id)) =
+      tuple((u16, u16, u16, u32, string))(input)?;
+    let (input, custom_format) = cond(
+      save_version >= SAVE_VERSION_ADDED_CUSTOM_VERSIONS,
+      tuple((u32, parse_custom_format_data)),
+    )(input)?;
+    let (input, save_game_type) = string(input)?;
+
+    let (custom_format_version, custom_format_data) = match custom_format {
+      Some((version, data)) => (Some(version), data),
+      None => (None, vec![]),
+    };
+    Ok((
+      input,
+      Metadata {
         save_version,
         package_version,
+        package_version_ue5,
         engine_major,
         engine_minor,
         engine_patch,
@@ -74,17 +151,26 @@ pub fn parse_metadata<
         custom_format_data,
         save_game_type,
       },
-    ),
-  )(input)
+    ))
+  })
 }
 
 fn parse_custom_format_data<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
   input: &'a [u8],
-) -> IResult<&[u8], HashMap<Guid, u32>, E> {
+) -> IResult<&'a [u8], Vec<(Guid, u32)>, E> {
   context(
     "custom format data",
-    map(length_count(u32, tuple((parse_guid, u32))), |v| {
-      v.into_iter().collect()
-    }),
+    length_count(u32, tuple((parse_guid, u32))),
   )(input)
 }
+
+fn serialize_custom_format_data<S: Serializer>(
+  custom_format_data: &[(Guid, u32)],
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  serializer.collect_map(
+    custom_format_data
+      .iter()
+      .map(|(guid, version)| (guid, version)),
+  )
+}
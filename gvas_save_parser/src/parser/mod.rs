@@ -1,46 +1,186 @@
+mod context;
+mod custom_version;
 mod error;
 mod metadata;
+mod options;
+mod path;
 mod primitives;
 mod properties;
+mod trace;
+mod warning;
 
+use context::ParseContext;
+use error::ParseFailure;
 use nom::{
   bytes::complete::tag,
-  combinator::map,
-  error::{ContextError, FromExternalError, ParseError},
-  sequence::{preceded, tuple},
-  IResult,
+  error::{ContextError, ErrorKind, FromExternalError, ParseError},
+  sequence::preceded,
+  Err, IResult,
 };
 use serde::Serialize;
 use std::{
-  collections::HashMap,
+  cell::RefCell,
+  rc::Rc,
   string::{FromUtf16Error, FromUtf8Error},
 };
 
-pub use error::SaveFileParseError;
+pub use context::ParseState;
+pub use custom_version::{custom_version_guid, custom_version_name, KNOWN_CUSTOM_VERSIONS};
+pub use error::{InputError, ParseErrorKind, PartialParseError, SaveFileParseError};
 pub use metadata::*;
+pub use options::{ParseMode, ParseOptions};
+pub use path::{PathSegment, PropertyPath};
+pub use primitives::Guid;
 pub use properties::*;
+pub use trace::ParseTraceEntry;
+pub use warning::ParseWarning;
 
 #[derive(Debug, Serialize)]
 pub struct SaveFile {
   pub metadata: Metadata,
-  pub properties: HashMap<String, Property>,
+  pub properties: PropertyMap,
+  /**
+   * Everything after the root property map, which is normally a 4-byte zero
+   * the engine pads the end of the save with.
+   */
+  #[serde(skip)]
+  pub trailer: Vec<u8>,
+  /**
+   * Everything which was not understood while parsing the save in lenient
+   * mode. This is ignored when writing.
+   */
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub warnings: Vec<ParseWarning>,
+  /**
+   * Every property parsed, if `ParseOptions::trace` is on. This is ignored
+   * when writing.
+   */
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub trace: Vec<ParseTraceEntry>,
 }
 
+/**
+ * An error which stopped a root property, or the end of the save, from being
+ * parsed, with the path of the property which failed and the number of
+ * properties traced before it.
+ */
+pub struct RootFailure<E> {
+  pub error: Err<E>,
+  pub property_path: Option<PropertyPath>,
+  pub trace_len: usize,
+}
+
+/**
+ * A parsed save and everything which failed to parse in it.
+ */
+pub type ParsedSave<E> = (SaveFile, Vec<RootFailure<E>>);
+
+/**
+ * Parses a whole save. This only fails if the metadata can't be parsed, and
+ * otherwise returns the save along with anything which went wrong after the
+ * metadata. If `skip_failed` is set, a root property which fails to parse is
+ * kept as an unknown property, by skipping over the size declared in its tag,
+ * and the properties after it are parsed as usual. Otherwise, or if it can't
+ * be skipped over, the save only has the properties before it.
+ */
 pub fn root<
   'a,
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  options: Rc<ParseOptions>,
+  state: Rc<RefCell<ParseState>>,
+  skip_failed: bool,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], ParsedSave<E>, E> {
+  move |input| {
+    let input_len = input.len();
+    let (mut input, metadata) =
+      preceded(tag(b"GVAS"), parse_metadata(options.max_string_length))(input)?;
+    let ctx = ParseContext::new(
+      metadata.archive_version(),
+      options.clone(),
+      input_len,
+      state.clone(),
+    );
+    let failure = |error| {
+      let (property_path, trace_len) = ctx.take_failure();
+      RootFailure {
+        error,
+        property_path,
+        trace_len,
+      }
+    };
+
+    let mut properties = vec![];
+    let mut failures = vec![];
+    let trailer = loop {
+      let (rest, (parsed, error)) = parse_properties(ctx.clone())(input)?;
+      properties.extend(parsed);
+      input = rest;
+      let Some(e) = error else {
+        failures.extend(trailer_error(&ctx, input).map(failure));
+        break input;
+      };
+      failures.push(failure(e.map(|e| E::add_context(input, "property map", e))));
+      if !skip_failed {
+        break &[][..];
+      }
+      match skip_property::<E>(options.max_string_length)(input) {
+        Ok((rest, property)) => {
+          properties.push(property);
+          input = rest;
+        }
+        Err(_) => break &[][..],
+      }
+    };
+
+    let mut state = state.borrow_mut();
+    let warnings = std::mem::take(&mut state.warnings);
+    let trace = std::mem::take(&mut state.trace);
+    Ok((
+      &input[input.len()..],
+      (
+        SaveFile {
+          metadata,
+          properties: properties.into_iter().collect(),
+          trailer: trailer.to_vec(),
+          warnings,
+          trace,
+        },
+        failures,
+      ),
+    ))
+  }
+}
+
+/**
+ * Checks the bytes after the root property map, which are either ignored, a
+ * warning in lenient mode, or an error.
+ */
+fn trailer_error<
+  'a,
+  E: ParseError<&'a [u8]> + ContextError<&'a [u8]> + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], SaveFile, E> {
-  map(
-    preceded(tag(b"GVAS"), tuple((parse_metadata, parse_property_map))),
-    |(metadata, properties)| SaveFile {
-      metadata,
-      properties,
-    },
-  )(input)
+  ctx: &ParseContext,
+  trailer: &'a [u8],
+) -> Option<Err<E>> {
+  if matches!(trailer, [] | [0, 0, 0, 0]) || ctx.options.allow_trailing_bytes {
+    return None;
+  }
+  let message = format!(
+    "Unexpected {} bytes after the root property map",
+    trailer.len()
+  );
+  if ctx.options.is_lenient() {
+    ctx.warn(trailer, message);
+    return None;
+  }
+  Some(Err::Error(E::from_external_error(
+    trailer,
+    ErrorKind::Eof,
+    ParseFailure::new(ParseErrorKind::TrailingData, message),
+  )))
 }
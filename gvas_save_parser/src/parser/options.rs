@@ -0,0 +1,140 @@
+use super::primitives::DEFAULT_MAX_STRING_LENGTH;
+use std::collections::HashMap;
+
+/**
+ * How to handle properties which can't be parsed.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+  /**
+   * Fail the whole parse.
+   */
+  #[default]
+  Strict,
+  /**
+   * Keep the raw bytes of the property, as declared by the size in its tag,
+   * and carry on with the next one. Each property kept this way is recorded
+   * as a warning on the save.
+   */
+  Lenient,
+}
+
+/**
+ * Options which change how a save is parsed.
+ */
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+  pub mode: ParseMode,
+  /**
+   * Whether to check that each property value took up exactly the size
+   * declared in its tag, which catches types which are read wrongly before
+   * they misalign everything after them. This is on by default.
+   */
+  pub verify_sizes: bool,
+  /**
+   * Whether to accept bytes after the root property map other than the
+   * 4-byte zero the engine writes there. These are usually a sign of a
+   * corrupted or concatenated file. They are kept in the trailer of the save
+   * either way.
+   */
+  pub allow_trailing_bytes: bool,
+  /**
+   * The longest string to accept, in characters including the
+   * null-terminator. Nothing in a save comes close to the default, so longer
+   * strings mean the parser has lost its place. Lengths are stored as an
+   * i32, so anything over i32::MAX is the same as i32::MAX.
+   */
+  pub max_string_length: u32,
+  /**
+   * The struct type of the structs in maps and sets, keyed by property path,
+   * since it is not recorded in the save. Paths are the property names from
+   * the root of the save joined by ".", with ".Key" or ".Value" appended for
+   * the keys and values of maps, e.g. `UnLockedMissionParameters.Key`. Array
+   * indices are not part of the path, so a hint applies to every element.
+   *
+   * Without a hint, struct map keys are read as GUIDs, struct map values as
+   * property maps, and struct set elements as whichever of those fits the
+   * size of the set.
+   */
+  pub struct_types: HashMap<String, String>,
+  /**
+   * The enum type of the bytes in arrays, maps and sets, keyed by property
+   * path in the same way as `struct_types`. Bytes with an enum type are
+   * stored as the name of the enum value rather than as a plain byte, which
+   * the save only records for byte properties outside of containers.
+   *
+   * Without a hint, bytes are read as whichever of the two fits the size of
+   * their container, and map keys and values are read as plain bytes when
+   * the size of the map can't tell them apart.
+   */
+  pub enum_types: HashMap<String, String>,
+  /**
+   * Whether to record each property as it is parsed, with its path, offset,
+   * type and size, which shows how far the parser got through a save which
+   * fails to parse. This is off by default.
+   */
+  pub trace: bool,
+}
+
+impl Default for ParseOptions {
+  fn default() -> Self {
+    Self {
+      mode: ParseMode::default(),
+      verify_sizes: true,
+      allow_trailing_bytes: false,
+      max_string_length: DEFAULT_MAX_STRING_LENGTH,
+      struct_types: HashMap::new(),
+      enum_types: HashMap::new(),
+      trace: false,
+    }
+  }
+}
+
+impl ParseOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_mode(mut self, mode: ParseMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  pub fn with_verify_sizes(mut self, verify_sizes: bool) -> Self {
+    self.verify_sizes = verify_sizes;
+    self
+  }
+
+  pub fn with_allow_trailing_bytes(mut self, allow_trailing_bytes: bool) -> Self {
+    self.allow_trailing_bytes = allow_trailing_bytes;
+    self
+  }
+
+  pub fn with_max_string_length(mut self, max_string_length: u32) -> Self {
+    self.max_string_length = max_string_length;
+    self
+  }
+
+  pub fn with_struct_type(mut self, path: &str, struct_type: &str) -> Self {
+    self
+      .struct_types
+      .insert(path.to_owned(), struct_type.to_owned());
+    self
+  }
+
+  pub fn with_enum_type(mut self, path: &str, enum_type: &str) -> Self {
+    self
+      .enum_types
+      .insert(path.to_owned(), enum_type.to_owned());
+    self
+  }
+
+  pub fn with_trace(mut self, trace: bool) -> Self {
+    self.trace = trace;
+    self
+  }
+
+  pub fn is_lenient(&self) -> bool {
+    self.mode == ParseMode::Lenient
+  }
+}
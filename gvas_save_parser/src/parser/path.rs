@@ -0,0 +1,82 @@
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
+
+/**
+ * One step along the path to a property, either the name of a property or
+ * map key or value, or the index of an array element, set element or map
+ * entry.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+  Name(String),
+  Index(usize),
+}
+
+/**
+ * Where a property is in a save, from the root property map in. This is
+ * displayed and serialized as the names joined by "." with each index in
+ * brackets, e.g. `SchematicSave.ForceUnlocked[3]`.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PropertyPath(Vec<PathSegment>);
+
+impl PropertyPath {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn segments(&self) -> &[PathSegment] {
+    &self.0
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub fn push(&mut self, segment: PathSegment) {
+    self.0.push(segment);
+  }
+
+  /**
+   * The names along the path without any indices, which is the form of the
+   * paths struct types are given for in `ParseOptions`.
+   */
+  pub fn names(&self) -> String {
+    join_names(&self.0)
+  }
+}
+
+/**
+ * Joins the names in the given segments with ".", leaving out the indices.
+ * This is shared with `ParseContext`, which looks up types by the names along
+ * a path without building the whole path first.
+ */
+pub fn join_names<'s>(segments: impl IntoIterator<Item = &'s PathSegment>) -> String {
+  let names: Vec<&str> = segments
+    .into_iter()
+    .filter_map(|segment| match segment {
+      PathSegment::Name(name) => Some(name.as_str()),
+      PathSegment::Index(_) => None,
+    })
+    .collect();
+  names.join(".")
+}
+
+impl Display for PropertyPath {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (i, segment) in self.0.iter().enumerate() {
+      match segment {
+        PathSegment::Name(name) if i == 0 => write!(f, "{}", name)?,
+        PathSegment::Name(name) => write!(f, ".{}", name)?,
+        PathSegment::Index(index) => write!(f, "[{}]", index)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Serialize for PropertyPath {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
@@ -1,21 +1,23 @@
+use super::error::{ParseErrorKind, ParseFailure};
 use nom::{
-  combinator::{flat_map, map, map_res, verify},
+  combinator::{flat_map, map, map_res},
   error::{context, ContextError, FromExternalError, ParseError},
   multi::count,
   number::complete::{le_i32 as i32, le_u16 as u16, le_u32 as u32, le_u8 as u8},
+  sequence::tuple,
   IResult,
 };
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 use std::{
-  ops::Range,
+  fmt::Display,
   string::{FromUtf16Error, FromUtf8Error},
 };
 
 /**
- * The maximum acceptable size of a string is 2^16. String lengths are negative
- * if the string is encoded as a wide string.
+ * The maximum acceptable size of a string, unless `ParseOptions` says
+ * otherwise.
  */
-const VALID_STRING_LENGTH: Range<i32> = -(1 << 16)..(1 << 16);
+pub const DEFAULT_MAX_STRING_LENGTH: u32 = 1 << 16;
 
 /**
  * Strings of length > 0 are laid out in the structure:
@@ -29,50 +31,92 @@ const VALID_STRING_LENGTH: Range<i32> = -(1 << 16)..(1 << 16);
  * be an optimization for file size.
  *
  * This will remove the trailing null-byte from the resulting string slice if
- * necessary
+ * necessary. Strings with a length over `max_length`, counting the
+ * null-terminator, are rejected, which is usually the first sign of reading a
+ * string from the wrong place.
  */
 pub fn parse_string<
   'a,
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
-    + FromExternalError<&'a [u8], FromUtf8Error>,
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], String, E> {
-  context(
-    "string",
-    map(
-      flat_map(verify(i32, |l| VALID_STRING_LENGTH.contains(l)), |l| {
-        move |input| {
-          if l < 0 {
-            map_res(count(u16, -l as usize), |v| String::from_utf16(&v))(input)
+  max_length: u32,
+) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], String, E> + Copy {
+  // No length can be over i32::MAX, which keeps i32::MIN out too
+  let max_length = max_length.min(i32::MAX as u32);
+  move |input| {
+    context(
+      "string",
+      map(
+        flat_map(
+          map_res(i32, |l| {
+            // String lengths are negative if the string is encoded as a wide
+            // string, so the maximum applies in both directions
+            if l.unsigned_abs() <= max_length {
+              Ok(l)
+            } else {
+              Err(ParseFailure::new(
+                ParseErrorKind::InvalidStringLength,
+                format!(
+                  "String length {} is over the maximum of {}",
+                  l.unsigned_abs(),
+                  max_length
+                ),
+              ))
+            }
+          }),
+          |l| {
+            move |input| {
+              let length = l.unsigned_abs() as usize;
+              if l < 0 {
+                map_res(count(u16, length), |v| String::from_utf16(&v))(input)
+              } else {
+                map_res(count(u8, length), String::from_utf8)(input)
+              }
+            }
+          },
+        ),
+        |s| {
+          if s.is_empty() {
+            // Avoid underflow when l == 0
+            s
           } else {
-            map_res(count(u8, l as usize), |v| String::from_utf8(v))(input)
+            // ignore the last byte of the string (\0)
+            s[..s.len() - 1].to_string()
           }
-        }
-      }),
-      |s| {
-        if s.len() == 0 {
-          // Avoid underflow when l == 0
-          s
-        } else {
-          // ignore the last byte of the string (\0)
-          s[..s.len() - 1].to_string()
-        }
-      },
-    ),
-  )(input)
+        },
+      ),
+    )(input)
+  }
 }
 
-#[derive(PartialEq, Hash, Eq, Debug, Serialize)]
-pub struct Guid(String);
+#[derive(PartialEq, Hash, Eq, Debug, Clone, Copy, Default)]
+pub struct Guid(pub [u32; 4]);
+
+impl Display for Guid {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let [a, b, c, d] = self.0;
+    write!(f, "{a:08X}-{b:08X}-{c:08X}-{d:08X}")
+  }
+}
+
+impl Serialize for Guid {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    serializer.collect_str(self)
+  }
+}
 
 /**
  * GUIDs are 4 4-byte groups of hex values encoded in the save file as ints,
  * meaning that each individual group has little endian encoding. We therefore
- * read the value as those 4 ints to decode endian-ness before reconstructing
- * it into the standard string representation.
+ * read the value as those 4 ints to decode endian-ness, and only reconstruct
+ * the standard string representation when displaying or serializing it.
  *
  * Example parsed GUID
  *   4C4F1A50-42CB24CC-2A7F28B0-0D12AEF9
@@ -82,16 +126,11 @@ pub struct Guid(String);
  */
 pub fn parse_guid<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
   input: &'a [u8],
-) -> IResult<&[u8], Guid, E> {
+) -> IResult<&'a [u8], Guid, E> {
   context(
     "guid",
-    map(count(u32, 4), |v| {
-      Guid(
-        v.into_iter()
-          .map(|i| format!("{i:08X}"))
-          .collect::<Vec<_>>()
-          .join("-"),
-      )
+    map(tuple((u32, u32, u32, u32)), |(a, b, c, d)| {
+      Guid([a, b, c, d])
     }),
   )(input)
 }
@@ -102,16 +141,19 @@ pub fn parse_guid<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
  */
 pub fn parse_bool<
   'a,
-  E: ParseError<&'a [u8]> + ContextError<&'a [u8]> + FromExternalError<&'a [u8], String>,
+  E: ParseError<&'a [u8]> + ContextError<&'a [u8]> + FromExternalError<&'a [u8], ParseFailure>,
 >(
   input: &'a [u8],
-) -> IResult<&[u8], bool, E> {
+) -> IResult<&'a [u8], bool, E> {
   context(
     "bool",
     map_res(u8, |i| match i {
       0 => Ok(false),
       1 => Ok(true),
-      n => Err(format!("Invalid boolean value {}", n)),
+      n => Err(ParseFailure::new(
+        ParseErrorKind::InvalidBool,
+        format!("Invalid boolean value {}", n),
+      )),
     }),
   )(input)
 }
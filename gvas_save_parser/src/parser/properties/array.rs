@@ -1,13 +1,25 @@
-use super::{r#struct::parse_struct_value, ArrayPropertyValue, Property};
-use crate::parser::primitives::parse_string;
+use super::{
+  count_indexed, is_byte_labels, parse_soft_object_path,
+  r#struct::parse_struct_value,
+  size_mismatch,
+  tag::{parse_property_tag, PropertyTagData},
+  text::parse_text,
+  ArrayProperty, ArrayPropertyValue, ArrayPropertyValues, ArrayStructTag, BytePropertyValue,
+  Property,
+};
+use crate::parser::{
+  context::ParseContext,
+  error::{ParseErrorKind, ParseFailure},
+  primitives::{parse_bool, parse_guid, parse_string},
+};
 use nom::{
   bytes::complete::take,
   combinator::{map, map_res},
   error::{context, ContextError, ErrorKind, FromExternalError, ParseError},
-  multi::count,
-  number::complete::le_i32 as i32,
-  number::complete::le_u32 as u32,
-  number::complete::le_u64 as u64,
+  number::complete::{
+    le_f32 as f32, le_f64 as f64, le_i16 as i16, le_i32 as i32, le_i64 as i64, le_i8 as i8,
+    le_u16 as u16, le_u32 as u32, le_u64 as u64,
+  },
   Err, IResult, ToUsize,
 };
 use std::string::{FromUtf16Error, FromUtf8Error};
@@ -18,29 +30,199 @@ pub fn parse_array_property<
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&'a [u8], Property, E> {
-  context("array property", |input| {
-    let (input, property_type) = parse_string(input)?;
-    let (input, _) = take(1u8)(input)?;
-    let (input, property_count) = u32(input)?;
+  ctx: ParseContext,
+  property_type: String,
+  size: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let max_string_length = ctx.options.max_string_length;
+  let string = parse_string(max_string_length);
+  move |input| {
+    context("array property", |input| {
+      let (input, property_count) = u32(input)?;
+
+      let (input, struct_tag) = match property_type.as_str() {
+        "StructProperty" => map(parse_array_struct_tag(max_string_length), Some)(input)?,
+        _ => (input, None),
+      };
 
-    let (input, values) = match property_type.as_str() {
-      "StructProperty" => parse_struct_array(property_count)(input)?,
-      "IntProperty" => parse_int_array(property_count)(input)?,
-      "ObjectProperty" => parse_object_array(property_count)(input)?,
-      e => {
-        return Err(Err::Error(E::from_external_error(
-          input,
-          ErrorKind::Fail,
-          format!("Unhandled array property type {}", e),
-        )))
-      }
-    };
-    Ok((input, Property::Array(values)))
-  })(input)
+      let (input, values) = match (property_type.as_str(), &struct_tag) {
+        ("StructProperty", Some(tag)) => {
+          let (rest, values) =
+            parse_struct_array(ctx.clone(), tag.struct_type.clone(), property_count)(input)?;
+          if let Some(message) = size_mismatch(&ctx, input, rest, tag.tag.size) {
+            return Err(Err::Error(E::from_external_error(
+              input,
+              ErrorKind::Verify,
+              ParseFailure::new(
+                ParseErrorKind::SizeMismatch,
+                format!("The elements of {}", message),
+              ),
+            )));
+          }
+          (rest, ArrayPropertyValues::Elements(values))
+        }
+        ("Int8Property", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(i8, ArrayPropertyValue::Int8),
+        )(input)?,
+        ("Int16Property", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(i16, ArrayPropertyValue::Int16),
+        )(input)?,
+        ("IntProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(i32, ArrayPropertyValue::Int),
+        )(input)?,
+        ("Int64Property", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(i64, ArrayPropertyValue::Int64),
+        )(input)?,
+        ("UInt16Property", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(u16, ArrayPropertyValue::UInt16),
+        )(input)?,
+        ("UInt32Property", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(u32, ArrayPropertyValue::UInt32),
+        )(input)?,
+        ("UInt64Property", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(u64, ArrayPropertyValue::UInt64),
+        )(input)?,
+        ("FloatProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(f32, ArrayPropertyValue::Float),
+        )(input)?,
+        ("DoubleProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(f64, ArrayPropertyValue::Double),
+        )(input)?,
+        ("BoolProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(parse_bool, ArrayPropertyValue::Bool),
+        )(input)?,
+        ("StrProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(string, ArrayPropertyValue::Str),
+        )(input)?,
+        ("NameProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(string, ArrayPropertyValue::Name),
+        )(input)?,
+        // The enum type of an enum array is not recorded, only the value names
+        ("EnumProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(string, ArrayPropertyValue::Enum),
+        )(input)?,
+        ("TextProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(parse_text(max_string_length), ArrayPropertyValue::Text),
+        )(input)?,
+        ("ObjectProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(string, ArrayPropertyValue::Object),
+        )(input)?,
+        ("SoftObjectProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(
+            parse_soft_object_path(max_string_length),
+            ArrayPropertyValue::SoftObject,
+          ),
+        )(input)?,
+        ("SoftClassPathProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(
+            parse_soft_object_path(max_string_length),
+            ArrayPropertyValue::SoftClassPath,
+          ),
+        )(input)?,
+        ("LazyObjectProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(parse_guid, ArrayPropertyValue::LazyObject),
+        )(input)?,
+        ("InterfaceProperty", _) => parse_element_array(
+          ctx.clone(),
+          property_count,
+          map(string, ArrayPropertyValue::Interface),
+        )(input)?,
+        ("ByteProperty", _) => parse_byte_array(ctx.clone(), property_count, size)(input)?,
+        (e, _) => {
+          return Err(Err::Error(E::from_external_error(
+            input,
+            ErrorKind::Fail,
+            ParseFailure::new(
+              ParseErrorKind::UnknownPropertyType,
+              format!("Unhandled array property type {}", e),
+            ),
+          )))
+        }
+      };
+      Ok((
+        input,
+        Property::Array(ArrayProperty {
+          element_type: property_type.to_owned(),
+          struct_tag,
+          values,
+        }),
+      ))
+    })(input)
+  }
+}
+
+/**
+ * Struct arrays repeat a full property tag ahead of their elements, the size
+ * of which is the size of all of the elements.
+ */
+fn parse_array_struct_tag<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], ArrayStructTag, E> {
+  context(
+    "struct array tag",
+    map_res(
+      parse_property_tag(max_string_length),
+      |header| match header.data {
+        PropertyTagData::Struct { struct_type, .. } => Ok(ArrayStructTag {
+          name: header.name,
+          struct_type,
+          tag: header.tag,
+        }),
+        _ => Err(ParseFailure::new(
+          ParseErrorKind::UnknownPropertyType,
+          format!(
+            "Unhandled struct array property type {}",
+            header.property_type
+          ),
+        )),
+      },
+    ),
+  )
 }
 
 fn parse_struct_array<
@@ -49,60 +231,74 @@ fn parse_struct_array<
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
+  ctx: ParseContext,
+  struct_type: String,
   property_count: u32,
 ) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<ArrayPropertyValue>, E> {
   move |input| {
-    context("struct array", |input| {
-      let (input, _name) = parse_string(input)?;
-      let (input, _property_type) =
-        map_res(parse_string, |property_type| match property_type.as_str() {
-          "StructProperty" => Ok(property_type),
-          p => Err(format!("Unhandled struct array property type {}", p)),
-        })(input)?;
-      let (input, _length) = u64(input)?;
-
-      let (input, struct_inner_property_type) = parse_string(input)?;
-      let (input, _) = take(17u8)(input)?;
-
-      count(
+    context(
+      "struct array",
+      count_indexed(ctx.clone(), property_count.to_usize(), |ctx, input| {
         map(
-          parse_struct_value(struct_inner_property_type),
+          parse_struct_value(ctx, struct_type.clone()),
           ArrayPropertyValue::Struct,
-        ),
-        property_count.to_usize(),
-      )(input)
-    })(input)
+        )(input)
+      }),
+    )(input)
   }
 }
 
-fn parse_int_array<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+fn parse_element_array<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  ctx: ParseContext,
   property_count: u32,
-) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<ArrayPropertyValue>, E> {
-  move |input| {
-    context("int array", |input| {
-      count(map(i32, ArrayPropertyValue::Int), property_count.to_usize())(input)
-    })(input)
-  }
+  mut element: impl FnMut(&'a [u8]) -> IResult<&'a [u8], ArrayPropertyValue, E>,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], ArrayPropertyValues, E> {
+  context(
+    "array elements",
+    map(
+      count_indexed(ctx, property_count.to_usize(), move |_, input| {
+        element(input)
+      }),
+      ArrayPropertyValues::Elements,
+    ),
+  )
 }
 
-fn parse_object_array<
+/**
+ * Plain bytes take up exactly one byte each after the u32 element count, and
+ * are kept as they are rather than as an element per byte.
+ */
+fn parse_byte_array<
   'a,
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
-    + FromExternalError<&'a [u8], FromUtf8Error>,
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
+  ctx: ParseContext,
   property_count: u32,
-) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<ArrayPropertyValue>, E> {
+  size: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], ArrayPropertyValues, E> {
   move |input| {
-    context(
-      "object array",
-      count(
-        map(parse_string, ArrayPropertyValue::Object),
-        property_count.to_usize(),
-      ),
-    )(input)
+    let plain_size = 4 + u64::from(property_count);
+    if !is_byte_labels(&ctx, Some(plain_size), size) {
+      context(
+        "byte array",
+        map(take(property_count), |bytes: &[u8]| {
+          ArrayPropertyValues::Bytes(bytes.to_vec())
+        }),
+      )(input)
+    } else {
+      parse_element_array(
+        ctx.clone(),
+        property_count,
+        map(parse_string(ctx.options.max_string_length), |s| {
+          ArrayPropertyValue::Byte(BytePropertyValue::Label(s))
+        }),
+      )(input)
+    }
   }
 }
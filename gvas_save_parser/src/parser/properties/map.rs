@@ -1,63 +1,261 @@
-use super::{parse_property_map, MapPropertyKey, MapPropertyValue, Property};
-use crate::parser::primitives::{parse_guid, parse_string};
+use super::{
+  count_indexed, is_byte_labels, parse_property_map, parse_soft_object_path,
+  r#struct::parse_struct_value, read_u32_at, text::parse_text, BytePropertyValue, MapProperty,
+  MapPropertyKey, MapPropertyValue, Property, StructPropertyValue,
+};
+use crate::parser::{
+  context::ParseContext,
+  error::{ParseErrorKind, ParseFailure},
+  primitives::{parse_bool, parse_guid, parse_string},
+};
 use nom::{
-  bytes::complete::take,
   combinator::map,
   error::{context, ContextError, ErrorKind, FromExternalError, ParseError},
-  multi::length_count,
-  number::complete::{le_f32 as f32, le_i32 as i32, le_i8 as i8, le_u32 as u32},
+  number::complete::{
+    le_f32 as f32, le_f64 as f64, le_i16 as i16, le_i32 as i32, le_i64 as i64, le_i8 as i8,
+    le_u16 as u16, le_u32 as u32, le_u64 as u64, le_u8 as u8,
+  },
   sequence::tuple,
-  Err, IResult,
+  Err, IResult, ToUsize,
 };
 use std::string::{FromUtf16Error, FromUtf8Error};
 
+/**
+ * Maps are laid out in the structure:
+ *   [u32 | keys to remove count][keys to remove][u32 | entry count][entries]
+ * where the keys to remove are only used when the map overrides a default
+ * value, and are almost always empty in saves.
+ *
+ * Byte keys and values are read as enum value names the same way as the
+ * elements of byte arrays and sets, using the size of the map when the keys
+ * and values are all of a fixed size.
+ */
 pub fn parse_map_property<
   'a,
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  ctx: ParseContext,
+  key_type: String,
+  value_type: String,
+  size: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  move |input| {
+    context("map property", |input| {
+      let plain_size = plain_byte_map_size(input, &key_type, &value_type);
+      let key_ctx = ctx.with_segment("Key");
+      let key_labels = key_type == "ByteProperty" && is_byte_labels(&key_ctx, plain_size, size);
+      let value_ctx = ctx.with_segment("Value");
+      let value_labels =
+        value_type == "ByteProperty" && is_byte_labels(&value_ctx, plain_size, size);
+
+      let (input, keys_to_remove_count) = u32(input)?;
+      let (input, keys_to_remove) = context(
+        "map keys to remove",
+        count_indexed(
+          ctx.clone(),
+          keys_to_remove_count.to_usize(),
+          |ctx, input| parse_map_key(ctx.with_segment("Key"), &key_type, key_labels)(input),
+        ),
+      )(input)?;
+
+      let (input, entry_count) = u32(input)?;
+      let (input, entries) = count_indexed(ctx.clone(), entry_count.to_usize(), |ctx, input| {
+        tuple((
+          parse_map_key(ctx.with_segment("Key"), &key_type, key_labels),
+          parse_map_value(ctx.with_segment("Value"), &value_type, value_labels),
+        ))(input)
+      })(input)?;
+
+      Ok((
+        input,
+        Property::Map(MapProperty {
+          key_type: key_type.to_owned(),
+          value_type: value_type.to_owned(),
+          keys_to_remove,
+          entries,
+        }),
+      ))
+    })(input)
+  }
+}
+
+fn parse_map_key<
+  'a,
+  'k,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context("map property", |input| {
-    let (input, (key_type, value_type)) = tuple((parse_string, parse_string))(input)?;
-    let (input, _) = take(5u8)(input)?;
-
-    map(
-      length_count(u32, move |input| {
-        let (input, key) = context("map property key", |input| match key_type.as_str() {
-          "StructProperty" => map(parse_guid, MapPropertyKey::Struct)(input),
-          "IntProperty" => map(i32, MapPropertyKey::Int)(input),
-          e => Err(Err::Error(E::from_external_error(
-            input,
-            ErrorKind::Fail,
-            format!("Unhandled map property key type {}", e),
-          ))),
-        })(input)?;
-
-        let (input, value) = context("map property value", |input| match value_type.as_str() {
-          "StructProperty" => map(parse_property_map, MapPropertyValue::Struct)(input),
-          "IntProperty" => map(i32, MapPropertyValue::Int)(input),
-          "FloatProperty" => map(f32, MapPropertyValue::Float)(input),
-          "BoolProperty" => map(parse_map_bool_property, MapPropertyValue::Bool)(input),
-          e => Err(Err::Error(E::from_external_error(
-            input,
-            ErrorKind::Fail,
-            format!("Unhandled map property value type {}", e),
-          ))),
-        })(input)?;
-
-        Ok((input, (key, value)))
-      }),
-      |d| Property::Map(d.into_iter().collect()),
-    )(input)
-  })(input)
+  ctx: ParseContext,
+  key_type: &'k str,
+  byte_labels: bool,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], MapPropertyKey, E> + 'k {
+  let max_string_length = ctx.options.max_string_length;
+  move |input| {
+    context("map property key", |input| match key_type {
+      // Struct keys are almost always GUIDs, unless told otherwise
+      "StructProperty" => map(
+        parse_struct_value(ctx.clone(), ctx.struct_type().unwrap_or("Guid").to_owned()),
+        MapPropertyKey::Struct,
+      )(input),
+      "Int8Property" => map(i8, MapPropertyKey::Int8)(input),
+      "Int16Property" => map(i16, MapPropertyKey::Int16)(input),
+      "IntProperty" => map(i32, MapPropertyKey::Int)(input),
+      "Int64Property" => map(i64, MapPropertyKey::Int64)(input),
+      "UInt16Property" => map(u16, MapPropertyKey::UInt16)(input),
+      "UInt32Property" => map(u32, MapPropertyKey::UInt32)(input),
+      "UInt64Property" => map(u64, MapPropertyKey::UInt64)(input),
+      "FloatProperty" => map(f32, MapPropertyKey::Float)(input),
+      "DoubleProperty" => map(f64, MapPropertyKey::Double)(input),
+      "BoolProperty" => map(parse_bool, MapPropertyKey::Bool)(input),
+      "ByteProperty" => map(
+        parse_map_byte(byte_labels, max_string_length),
+        MapPropertyKey::Byte,
+      )(input),
+      "StrProperty" => map(parse_string(max_string_length), MapPropertyKey::Str)(input),
+      "NameProperty" => map(parse_string(max_string_length), MapPropertyKey::Name)(input),
+      "EnumProperty" => map(parse_string(max_string_length), MapPropertyKey::Enum)(input),
+      "ObjectProperty" => map(parse_string(max_string_length), MapPropertyKey::Object)(input),
+      "SoftObjectProperty" => map(
+        parse_soft_object_path(max_string_length),
+        MapPropertyKey::SoftObject,
+      )(input),
+      "SoftClassPathProperty" => map(
+        parse_soft_object_path(max_string_length),
+        MapPropertyKey::SoftClassPath,
+      )(input),
+      "LazyObjectProperty" => map(parse_guid, MapPropertyKey::LazyObject)(input),
+      "InterfaceProperty" => map(parse_string(max_string_length), MapPropertyKey::Interface)(input),
+      e => Err(Err::Error(E::from_external_error(
+        input,
+        ErrorKind::Fail,
+        ParseFailure::new(
+          ParseErrorKind::UnknownPropertyType,
+          format!("Unhandled map property key type {}", e),
+        ),
+      ))),
+    })(input)
+  }
 }
 
-fn parse_map_bool_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
-  input: &'a [u8],
-) -> IResult<&'a [u8], bool, E> {
-  context("map bool property", map(i8, |i| i != 0))(input)
+fn parse_map_value<
+  'a,
+  'v,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  ctx: ParseContext,
+  value_type: &'v str,
+  byte_labels: bool,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], MapPropertyValue, E> + 'v {
+  let max_string_length = ctx.options.max_string_length;
+  move |input| {
+    context("map property value", |input| match value_type {
+      "StructProperty" => match ctx.struct_type() {
+        Some(struct_type) => map(
+          parse_struct_value(ctx.clone(), struct_type.to_owned()),
+          MapPropertyValue::Struct,
+        )(input),
+        None => map(parse_property_map(ctx.clone()), |value| {
+          MapPropertyValue::Struct(StructPropertyValue::Untyped(value))
+        })(input),
+      },
+      "Int8Property" => map(i8, MapPropertyValue::Int8)(input),
+      "Int16Property" => map(i16, MapPropertyValue::Int16)(input),
+      "IntProperty" => map(i32, MapPropertyValue::Int)(input),
+      "Int64Property" => map(i64, MapPropertyValue::Int64)(input),
+      "UInt16Property" => map(u16, MapPropertyValue::UInt16)(input),
+      "UInt32Property" => map(u32, MapPropertyValue::UInt32)(input),
+      "UInt64Property" => map(u64, MapPropertyValue::UInt64)(input),
+      "FloatProperty" => map(f32, MapPropertyValue::Float)(input),
+      "DoubleProperty" => map(f64, MapPropertyValue::Double)(input),
+      "BoolProperty" => map(parse_bool, MapPropertyValue::Bool)(input),
+      "ByteProperty" => map(
+        parse_map_byte(byte_labels, max_string_length),
+        MapPropertyValue::Byte,
+      )(input),
+      "StrProperty" => map(parse_string(max_string_length), MapPropertyValue::Str)(input),
+      "NameProperty" => map(parse_string(max_string_length), MapPropertyValue::Name)(input),
+      "EnumProperty" => map(parse_string(max_string_length), MapPropertyValue::Enum)(input),
+      "TextProperty" => map(parse_text(max_string_length), MapPropertyValue::Text)(input),
+      "ObjectProperty" => map(parse_string(max_string_length), MapPropertyValue::Object)(input),
+      "SoftObjectProperty" => map(
+        parse_soft_object_path(max_string_length),
+        MapPropertyValue::SoftObject,
+      )(input),
+      "SoftClassPathProperty" => map(
+        parse_soft_object_path(max_string_length),
+        MapPropertyValue::SoftClassPath,
+      )(input),
+      "LazyObjectProperty" => map(parse_guid, MapPropertyValue::LazyObject)(input),
+      "InterfaceProperty" => {
+        map(parse_string(max_string_length), MapPropertyValue::Interface)(input)
+      }
+      e => Err(Err::Error(E::from_external_error(
+        input,
+        ErrorKind::Fail,
+        ParseFailure::new(
+          ParseErrorKind::UnknownPropertyType,
+          format!("Unhandled map property value type {}", e),
+        ),
+      ))),
+    })(input)
+  }
+}
+
+/**
+ * The size of a map if its bytes are plain bytes, for maps whose keys and
+ * values all take up a fixed size.
+ */
+fn plain_byte_map_size(input: &[u8], key_type: &str, value_type: &str) -> Option<u64> {
+  let key_size = fixed_size(key_type)?;
+  let value_size = fixed_size(value_type)?;
+  let removed = read_u32_at(input, 0)?;
+  let count = read_u32_at(input, 4 + key_size * removed)?;
+  Some(8 + key_size * removed + (key_size + value_size) * count)
+}
+
+/**
+ * The size of a key or value of the given type, if every value of it takes up
+ * the same size, with bytes taken to be plain bytes.
+ */
+fn fixed_size(property_type: &str) -> Option<u64> {
+  match property_type {
+    "Int8Property" | "BoolProperty" | "ByteProperty" => Some(1),
+    "Int16Property" | "UInt16Property" => Some(2),
+    "IntProperty" | "UInt32Property" | "FloatProperty" => Some(4),
+    "Int64Property" | "UInt64Property" | "DoubleProperty" => Some(8),
+    "LazyObjectProperty" => Some(16),
+    _ => None,
+  }
+}
+
+fn parse_map_byte<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  labelled: bool,
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], BytePropertyValue, E> {
+  let string = parse_string(max_string_length);
+  move |input| {
+    if labelled {
+      map(string, BytePropertyValue::Label)(input)
+    } else {
+      map(u8, BytePropertyValue::Byte)(input)
+    }
+  }
 }
@@ -0,0 +1,327 @@
+use super::{
+  parse_property_map, Property, PropertyMap, PropertyTag, StructPropertyValue, TaggedProperty,
+};
+use crate::parser::{
+  context::ParseContext,
+  error::ParseFailure,
+  primitives::{parse_bool, Guid},
+  ArchiveVersion,
+};
+use nom::{
+  combinator::map,
+  error::{context, ContextError, FromExternalError, ParseError},
+  number::complete::{le_f32 as f32, le_f64 as f64, le_i32 as i32, le_u8 as u8},
+  sequence::tuple,
+  IResult,
+};
+use serde::Serialize;
+use std::string::{FromUtf16Error, FromUtf8Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Vector {
+  pub x: f64,
+  pub y: f64,
+  pub z: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Vector2D {
+  pub x: f64,
+  pub y: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Vector4 {
+  pub x: f64,
+  pub y: f64,
+  pub z: f64,
+  pub w: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Rotator {
+  pub pitch: f64,
+  pub yaw: f64,
+  pub roll: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Quat {
+  pub x: f64,
+  pub y: f64,
+  pub z: f64,
+  pub w: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LinearColor {
+  pub r: f32,
+  pub g: f32,
+  pub b: f32,
+  pub a: f32,
+}
+
+/**
+ * 8-bit colors are stored in BGRA order.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Color {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8,
+  pub a: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct IntPoint {
+  pub x: i32,
+  pub y: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct IntVector {
+  pub x: i32,
+  pub y: i32,
+  pub z: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BoundingBox {
+  pub min: Vector,
+  pub max: Vector,
+  pub is_valid: bool,
+}
+
+/**
+ * Unlike the other math structs, transforms are not stored natively but as a
+ * property map holding each of their components.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Transform {
+  pub rotation: Quat,
+  pub translation: Vector,
+  pub scale_3d: Vector,
+}
+
+impl Transform {
+  /**
+   * The properties a transform is stored as, in the order they are written.
+   */
+  pub fn to_properties(self) -> PropertyMap {
+    [
+      ("Rotation", StructPropertyValue::Quat(self.rotation)),
+      ("Translation", StructPropertyValue::Vector(self.translation)),
+      ("Scale3D", StructPropertyValue::Vector(self.scale_3d)),
+    ]
+    .into_iter()
+    .map(|(name, value)| {
+      (
+        name.to_owned(),
+        TaggedProperty {
+          tag: PropertyTag {
+            struct_guid: Some(Guid::default()),
+            ..Default::default()
+          },
+          value: Property::Struct(value),
+        },
+      )
+    })
+    .collect()
+  }
+
+  /**
+   * Reads a transform back out of its properties, if they are exactly the
+   * ones `to_properties` would write. Anything else is kept as a plain
+   * property map so that it is written back out unchanged.
+   */
+  fn from_properties(properties: &PropertyMap) -> Option<Transform> {
+    let names = properties.iter().map(|(name, _)| name.as_str());
+    if !names.eq(["Rotation", "Translation", "Scale3D"]) {
+      return None;
+    }
+
+    let struct_value = |name: &str| {
+      let property = properties.get(name)?;
+      let tag = &property.tag;
+      let is_default_tag = tag.array_index == 0
+        && tag.guid.is_none()
+        && tag.struct_guid.unwrap_or_default() == Guid::default();
+      match &property.value {
+        Property::Struct(s) if is_default_tag => Some(s),
+        _ => None,
+      }
+    };
+
+    match (
+      struct_value("Rotation")?,
+      struct_value("Translation")?,
+      struct_value("Scale3D")?,
+    ) {
+      (
+        StructPropertyValue::Quat(rotation),
+        StructPropertyValue::Vector(translation),
+        StructPropertyValue::Vector(scale_3d),
+      ) => Some(Transform {
+        rotation: *rotation,
+        translation: *translation,
+        scale_3d: *scale_3d,
+      }),
+      _ => None,
+    }
+  }
+}
+
+/**
+ * The components of vectors, rotators and quaternions are f64s when the save
+ * uses large world coordinates, and f32s otherwise. They are always held as
+ * f64s, which every f32 fits in exactly.
+ */
+fn parse_component<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  version: ArchiveVersion,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], f64, E> + Copy {
+  move |input| {
+    if version.large_world_coordinates {
+      f64(input)
+    } else {
+      map(f32, f64::from)(input)
+    }
+  }
+}
+
+pub fn parse_vector<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  version: ArchiveVersion,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vector, E> {
+  let c = parse_component(version);
+  context(
+    "vector",
+    map(tuple((c, c, c)), |(x, y, z)| Vector { x, y, z }),
+  )
+}
+
+pub fn parse_vector_2d<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  version: ArchiveVersion,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vector2D, E> {
+  let c = parse_component(version);
+  context("vector 2d", map(tuple((c, c)), |(x, y)| Vector2D { x, y }))
+}
+
+pub fn parse_vector_4<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  version: ArchiveVersion,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vector4, E> {
+  let c = parse_component(version);
+  context(
+    "vector 4",
+    map(tuple((c, c, c, c)), |(x, y, z, w)| Vector4 { x, y, z, w }),
+  )
+}
+
+pub fn parse_rotator<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  version: ArchiveVersion,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Rotator, E> {
+  let c = parse_component(version);
+  context(
+    "rotator",
+    map(tuple((c, c, c)), |(pitch, yaw, roll)| Rotator {
+      pitch,
+      yaw,
+      roll,
+    }),
+  )
+}
+
+pub fn parse_quat<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  version: ArchiveVersion,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Quat, E> {
+  let c = parse_component(version);
+  context(
+    "quat",
+    map(tuple((c, c, c, c)), |(x, y, z, w)| Quat { x, y, z, w }),
+  )
+}
+
+pub fn parse_linear_color<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], LinearColor, E> {
+  context(
+    "linear color",
+    map(tuple((f32, f32, f32, f32)), |(r, g, b, a)| LinearColor {
+      r,
+      g,
+      b,
+      a,
+    }),
+  )(input)
+}
+
+pub fn parse_color<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], Color, E> {
+  context(
+    "color",
+    map(tuple((u8, u8, u8, u8)), |(b, g, r, a)| Color { r, g, b, a }),
+  )(input)
+}
+
+pub fn parse_int_point<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], IntPoint, E> {
+  context(
+    "int point",
+    map(tuple((i32, i32)), |(x, y)| IntPoint { x, y }),
+  )(input)
+}
+
+pub fn parse_int_vector<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], IntVector, E> {
+  context(
+    "int vector",
+    map(tuple((i32, i32, i32)), |(x, y, z)| IntVector { x, y, z }),
+  )(input)
+}
+
+pub fn parse_box<
+  'a,
+  E: ParseError<&'a [u8]> + ContextError<&'a [u8]> + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  version: ArchiveVersion,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], BoundingBox, E> {
+  context(
+    "box",
+    map(
+      tuple((parse_vector(version), parse_vector(version), parse_bool)),
+      |(min, max, is_valid)| BoundingBox { min, max, is_valid },
+    ),
+  )
+}
+
+/**
+ * Transforms are parsed as a property map, and only become a `Transform` if
+ * the map holds exactly its components.
+ */
+pub fn parse_transform<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  ctx: ParseContext,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], StructPropertyValue, E> {
+  move |input| {
+    context(
+      "transform",
+      map(
+        parse_property_map(ctx.clone()),
+        |value| match Transform::from_properties(&value) {
+          Some(transform) => StructPropertyValue::Transform(transform),
+          None => StructPropertyValue::Foreign {
+            name: "Transform".to_owned(),
+            value,
+          },
+        },
+      ),
+    )(input)
+  }
+}
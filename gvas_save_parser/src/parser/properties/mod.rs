@@ -1,67 +1,345 @@
 mod array;
 mod map;
+mod math;
 mod set;
 mod r#struct;
+mod tag;
+mod text;
 
 use array::parse_array_property;
 use map::parse_map_property;
 use r#struct::parse_struct_property;
 use serde::{ser::SerializeMap, Serialize, Serializer};
 use set::parse_set_property;
+use tag::{parse_property_tag, PropertyTagData};
+use text::parse_text_property;
 
-use super::primitives::{parse_bool, parse_string, Guid};
+pub use math::{
+  BoundingBox, Color, IntPoint, IntVector, LinearColor, Quat, Rotator, Transform, Vector, Vector2D,
+  Vector4,
+};
+pub use text::{FText, FormatArgumentValue, NumberFormattingOptions, TextHistory};
+
+use super::{
+  context::ParseContext,
+  error::{ParseErrorKind, ParseFailure},
+  primitives::{parse_guid, parse_string, Guid},
+};
+use indexmap::IndexMap;
 use nom::{
   bytes::complete::take,
-  combinator::{cut, fail, map, verify},
-  error::{context, ContextError, FromExternalError, ParseError},
-  multi::{length_count, many_till},
-  number::complete::{le_f32 as f32, le_i32 as i32, le_u32 as u32},
-  sequence::{preceded, tuple},
-  IResult,
+  combinator::{cut, map, verify},
+  error::{context, ContextError, ErrorKind, FromExternalError, ParseError},
+  multi::length_count,
+  number::complete::{
+    le_f32 as f32, le_f64 as f64, le_i16 as i16, le_i32 as i32, le_i64 as i64, le_i8 as i8,
+    le_u16 as u16, le_u32 as u32, le_u64 as u64, le_u8 as u8,
+  },
+  sequence::tuple,
+  Err, IResult, ToUsize,
 };
 use std::{
-  collections::{HashMap, HashSet},
+  fmt::Display,
   string::{FromUtf16Error, FromUtf8Error},
 };
 
+/**
+ * Byte properties which are backed by an enum store the name of the enum value
+ * rather than the byte itself.
+ */
 #[derive(Debug, PartialEq, Eq, Hash, Serialize)]
 #[serde(untagged)]
+pub enum BytePropertyValue {
+  Byte(u8),
+  Label(String),
+}
+
+/**
+ * A reference to an asset which is not necessarily loaded, made up of the path
+ * of the asset and an optional path to a subobject within it. This is
+ * displayed and serialized the same way UE prints it, as the asset path
+ * followed by the sub path if there is one, since soft objects can be map
+ * keys.
+ */
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct SoftObjectPath {
+  pub asset_path_name: String,
+  pub sub_path_string: String,
+}
+
+impl Display for SoftObjectPath {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.asset_path_name)?;
+    if !self.sub_path_string.is_empty() {
+      write!(f, ":{}", self.sub_path_string)?;
+    }
+    Ok(())
+  }
+}
+
+impl Serialize for SoftObjectPath {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    serializer.collect_str(self)
+  }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
 pub enum MapPropertyKey {
-  Struct(Guid),
+  Struct(StructPropertyValue),
+  Int8(i8),
+  Int16(i16),
   Int(i32),
+  Int64(i64),
+  UInt16(u16),
+  UInt32(u32),
+  UInt64(u64),
+  Float(f32),
+  Double(f64),
+  Bool(bool),
+  Byte(BytePropertyValue),
+  Str(String),
+  Name(String),
+  Enum(String),
+  Object(String),
+  SoftObject(SoftObjectPath),
+  SoftClassPath(SoftObjectPath),
+  LazyObject(Guid),
+  Interface(String),
+}
+
+impl MapPropertyKey {
+  /**
+   * Whether the key serializes as a single string, number or bool, which JSON
+   * can still use as a map key.
+   */
+  fn is_scalar(&self) -> bool {
+    match self {
+      MapPropertyKey::Struct(value) => matches!(
+        value,
+        StructPropertyValue::DateTime(_)
+          | StructPropertyValue::Guid(_)
+          | StructPropertyValue::Timespan(_)
+      ),
+      _ => true,
+    }
+  }
 }
 
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum MapPropertyValue {
-  Struct(HashMap<String, Property>),
+  Struct(StructPropertyValue),
+  Int8(i8),
+  Int16(i16),
   Int(i32),
+  Int64(i64),
+  UInt16(u16),
+  UInt32(u32),
+  UInt64(u64),
   Float(f32),
+  Double(f64),
   Bool(bool),
+  Byte(BytePropertyValue),
+  Str(String),
+  Name(String),
+  Enum(String),
+  Text(FText),
+  Object(String),
+  SoftObject(SoftObjectPath),
+  SoftClassPath(SoftObjectPath),
+  LazyObject(Guid),
+  Interface(String),
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Serialize)]
+/**
+ * The key and value types are only recorded once in the map header, so they
+ * are kept alongside the entries in order to write empty maps back out.
+ *
+ * Entries are kept as a list in the order they appear in the save, since not
+ * every key type can be hashed, but are serialized as a map. Formats like JSON
+ * only allow strings as map keys though, so a map with any key which is more
+ * than a single value, like a vector or an untyped struct, is serialized as a
+ * list of `[key, value]` pairs instead. The keys to remove are not serialized
+ * at all.
+ */
+#[derive(Debug)]
+pub struct MapProperty {
+  pub key_type: String,
+  pub value_type: String,
+  pub keys_to_remove: Vec<MapPropertyKey>,
+  pub entries: Vec<(MapPropertyKey, MapPropertyValue)>,
+}
+
+impl Serialize for MapProperty {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    if self.entries.iter().all(|(k, _)| k.is_scalar()) {
+      serializer.collect_map(self.entries.iter().map(|(k, v)| (k, v)))
+    } else {
+      serializer.collect_seq(self.entries.iter().map(|(k, v)| (k, v)))
+    }
+  }
+}
+
+#[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum SetPropertyValue {
-  Struct(Guid),
+  Struct(StructPropertyValue),
+  Int8(i8),
+  Int16(i16),
+  Int(i32),
+  Int64(i64),
+  UInt16(u16),
+  UInt32(u32),
+  UInt64(u64),
+  Float(f32),
+  Double(f64),
+  Byte(BytePropertyValue),
+  Str(String),
+  Name(String),
+  Enum(String),
+  Object(String),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct SetProperty {
+  #[serde(skip)]
+  pub element_type: String,
+  #[serde(skip)]
+  pub elements_to_remove: Vec<SetPropertyValue>,
+  pub elements: Vec<SetPropertyValue>,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum ArrayPropertyValue {
+  Int8(i8),
+  Int16(i16),
   Int(i32),
+  Int64(i64),
+  UInt16(u16),
+  UInt32(u32),
+  UInt64(u64),
+  Float(f32),
+  Double(f64),
+  Bool(bool),
+  Str(String),
+  Name(String),
+  Enum(String),
+  Text(FText),
   Object(String),
   Struct(StructPropertyValue),
+  Byte(BytePropertyValue),
+  SoftObject(SoftObjectPath),
+  SoftClassPath(SoftObjectPath),
+  LazyObject(Guid),
+  Interface(String),
+}
+
+/**
+ * The elements of an array. Arrays of plain bytes, which are often large
+ * blobs of data, are kept as the bytes themselves rather than as an element
+ * per byte.
+ */
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ArrayPropertyValues {
+  Bytes(Vec<u8>),
+  Elements(Vec<ArrayPropertyValue>),
+}
+
+impl ArrayPropertyValues {
+  pub fn len(&self) -> usize {
+    match self {
+      ArrayPropertyValues::Bytes(bytes) => bytes.len(),
+      ArrayPropertyValues::Elements(elements) => elements.len(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+/**
+ * Arrays of structs repeat a property tag ahead of their elements which holds
+ * the name of the struct type, since it is not otherwise recorded per element.
+ */
+#[derive(Debug)]
+pub struct ArrayStructTag {
+  pub name: String,
+  pub struct_type: String,
+  pub tag: PropertyTag,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct ArrayProperty {
+  #[serde(skip)]
+  pub element_type: String,
+  #[serde(skip)]
+  pub struct_tag: Option<ArrayStructTag>,
+  pub values: ArrayPropertyValues,
 }
 
 #[derive(Debug)]
 pub enum StructPropertyValue {
   DateTime(i64),
   Guid(Guid),
+  Vector(Vector),
+  Vector2D(Vector2D),
+  Vector4(Vector4),
+  Rotator(Rotator),
+  Quat(Quat),
+  LinearColor(LinearColor),
+  Color(Color),
+  IntPoint(IntPoint),
+  IntVector(IntVector),
+  Box(BoundingBox),
+  Timespan(i64),
+  Transform(Transform),
   Foreign {
     name: String,
-    value: HashMap<String, Property>,
+    value: PropertyMap,
   },
+  /**
+   * Structs inside of sets and maps do not record their type, so unless it
+   * is known from elsewhere they can only be read as a plain property map.
+   */
+  Untyped(PropertyMap),
+}
+
+impl StructPropertyValue {
+  /**
+   * The name of the struct type, which untyped structs don't have.
+   */
+  pub fn struct_type(&self) -> Option<&str> {
+    Some(match self {
+      StructPropertyValue::DateTime(_) => "DateTime",
+      StructPropertyValue::Guid(_) => "Guid",
+      StructPropertyValue::Vector(_) => "Vector",
+      StructPropertyValue::Vector2D(_) => "Vector2D",
+      StructPropertyValue::Vector4(_) => "Vector4",
+      StructPropertyValue::Rotator(_) => "Rotator",
+      StructPropertyValue::Quat(_) => "Quat",
+      StructPropertyValue::LinearColor(_) => "LinearColor",
+      StructPropertyValue::Color(_) => "Color",
+      StructPropertyValue::IntPoint(_) => "IntPoint",
+      StructPropertyValue::IntVector(_) => "IntVector",
+      StructPropertyValue::Box(_) => "Box",
+      StructPropertyValue::Timespan(_) => "Timespan",
+      StructPropertyValue::Transform(_) => "Transform",
+      StructPropertyValue::Foreign { name, .. } => name,
+      StructPropertyValue::Untyped(_) => return None,
+    })
+  }
 }
 
 impl Serialize for StructPropertyValue {
@@ -72,6 +350,19 @@ impl Serialize for StructPropertyValue {
     match self {
       StructPropertyValue::DateTime(v) => serializer.serialize_i64(*v),
       StructPropertyValue::Guid(v) => v.serialize(serializer),
+      StructPropertyValue::Vector(v) => v.serialize(serializer),
+      StructPropertyValue::Vector2D(v) => v.serialize(serializer),
+      StructPropertyValue::Vector4(v) => v.serialize(serializer),
+      StructPropertyValue::Rotator(v) => v.serialize(serializer),
+      StructPropertyValue::Quat(v) => v.serialize(serializer),
+      StructPropertyValue::LinearColor(v) => v.serialize(serializer),
+      StructPropertyValue::Color(v) => v.serialize(serializer),
+      StructPropertyValue::IntPoint(v) => v.serialize(serializer),
+      StructPropertyValue::IntVector(v) => v.serialize(serializer),
+      StructPropertyValue::Box(v) => v.serialize(serializer),
+      StructPropertyValue::Timespan(v) => serializer.serialize_i64(*v),
+      StructPropertyValue::Transform(v) => v.serialize(serializer),
+      StructPropertyValue::Untyped(value) => value.serialize(serializer),
       StructPropertyValue::Foreign { name, value } => {
         let mut map = serializer.serialize_map(Some(1))?;
         map.serialize_entry(name, value)?;
@@ -83,26 +374,253 @@ impl Serialize for StructPropertyValue {
 
 #[derive(Debug, Serialize)]
 pub struct Delegate {
-  object_path: String,
-  function_name: String,
+  pub object_path: String,
+  pub function_name: String,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum Property {
+  Int8(i8),
+  Int16(i16),
   Int(i32),
+  Int64(i64),
+  UInt16(u16),
   UInt32(u32),
+  UInt64(u64),
   Bool(bool),
   Struct(StructPropertyValue),
-  Array(Vec<ArrayPropertyValue>),
+  Array(ArrayProperty),
   Float(f32),
+  Double(f64),
   MulticastInlineDelegate(Vec<Delegate>),
   Str(String),
-  Map(HashMap<MapPropertyKey, MapPropertyValue>),
-  Set(HashSet<SetPropertyValue>),
+  Map(MapProperty),
+  Set(SetProperty),
   Object(String),
-  Enum { name: String, value: String },
+  Enum {
+    name: String,
+    value: String,
+  },
   Name(String),
+  Byte {
+    name: String,
+    value: BytePropertyValue,
+  },
+  Text(FText),
+  SoftObject(SoftObjectPath),
+  SoftClassPath(SoftObjectPath),
+  LazyObject(Guid),
+  Interface(String),
+  /**
+   * A property which could not be parsed in lenient mode, kept as the bytes
+   * of the type specific data in its tag and the bytes of its value so that
+   * it can be written back out unchanged.
+   */
+  Unknown {
+    type_name: String,
+    #[serde(skip)]
+    tag_data: Vec<u8>,
+    raw: Vec<u8>,
+  },
+}
+
+/**
+ * Everything in a property tag which is not otherwise recoverable from the
+ * value of the property. The size is the declared size of the value, as read
+ * from the save; it is recomputed when writing.
+ */
+#[derive(Debug, Default, Clone)]
+pub struct PropertyTag {
+  pub size: u32,
+  pub array_index: u32,
+  pub struct_guid: Option<Guid>,
+  pub guid: Option<Guid>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct TaggedProperty {
+  #[serde(skip)]
+  pub tag: PropertyTag,
+  pub value: Property,
+}
+
+/**
+ * Properties keyed by name, kept in the order they appear in the save so that
+ * serializing a save is stable and writing it reproduces the original layout.
+ *
+ * Static array properties repeat the same name once per element, each with
+ * its own array index, so every name holds all of the properties written
+ * under it. These are serialized as a list when there is more than one.
+ */
+#[derive(Debug, Default)]
+pub struct PropertyMap(IndexMap<String, Vec<TaggedProperty>>);
+
+impl PropertyMap {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /**
+   * Gets the property with the given name, or its first element if it is a
+   * static array.
+   */
+  pub fn get(&self, name: &str) -> Option<&TaggedProperty> {
+    self.0.get(name).and_then(|properties| properties.first())
+  }
+
+  pub fn get_mut(&mut self, name: &str) -> Option<&mut TaggedProperty> {
+    self
+      .0
+      .get_mut(name)
+      .and_then(|properties| properties.first_mut())
+  }
+
+  /**
+   * Gets every property with the given name, which is more than one for
+   * static arrays.
+   */
+  pub fn get_all(&self, name: &str) -> &[TaggedProperty] {
+    self.0.get(name).map(Vec::as_slice).unwrap_or(&[])
+  }
+
+  /**
+   * Inserts a property, replacing the property with the same name and array
+   * index if there is one. Elements are kept in the order they were read, which
+   * is not necessarily by array index, so a new element goes before the first
+   * one with a higher index.
+   */
+  pub fn insert(&mut self, name: String, property: TaggedProperty) -> Option<TaggedProperty> {
+    let properties = self.0.entry(name).or_default();
+    let array_index = property.tag.array_index;
+    if let Some(existing) = properties
+      .iter_mut()
+      .find(|p| p.tag.array_index == array_index)
+    {
+      return Some(std::mem::replace(existing, property));
+    }
+    let i = properties
+      .iter()
+      .position(|p| p.tag.array_index > array_index)
+      .unwrap_or(properties.len());
+    properties.insert(i, property);
+    None
+  }
+
+  /**
+   * Iterates over every property, including each element of static arrays.
+   */
+  pub fn iter(&self) -> PropertyMapIter<'_> {
+    PropertyMapIter {
+      names: self.0.iter(),
+      properties: None,
+    }
+  }
+
+  /**
+   * The number of distinct property names.
+   */
+  pub fn len(&self) -> usize {
+    self.0.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+}
+
+impl FromIterator<(String, TaggedProperty)> for PropertyMap {
+  fn from_iter<T: IntoIterator<Item = (String, TaggedProperty)>>(iter: T) -> Self {
+    let mut map = PropertyMap::new();
+    for (name, property) in iter {
+      map.insert(name, property);
+    }
+    map
+  }
+}
+
+impl<'a> IntoIterator for &'a PropertyMap {
+  type Item = (&'a String, &'a TaggedProperty);
+  type IntoIter = PropertyMapIter<'a>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+pub struct PropertyMapIter<'a> {
+  names: indexmap::map::Iter<'a, String, Vec<TaggedProperty>>,
+  properties: Option<(&'a String, std::slice::Iter<'a, TaggedProperty>)>,
+}
+
+impl<'a> Iterator for PropertyMapIter<'a> {
+  type Item = (&'a String, &'a TaggedProperty);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some((name, properties)) = &mut self.properties {
+        if let Some(property) = properties.next() {
+          return Some((name, property));
+        }
+      }
+      let (name, properties) = self.names.next()?;
+      self.properties = Some((name, properties.iter()));
+    }
+  }
+}
+
+impl Serialize for PropertyMap {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    let mut map = serializer.serialize_map(Some(self.0.len()))?;
+    for (name, properties) in &self.0 {
+      match properties.as_slice() {
+        [property] => map.serialize_entry(name, property)?,
+        properties => map.serialize_entry(name, properties)?,
+      }
+    }
+    map.end()
+  }
+}
+
+impl Property {
+  /**
+   * The type name written in the property tag, which is used to pick the
+   * parser for the value that follows it.
+   */
+  pub fn type_name(&self) -> &str {
+    match self {
+      Property::Int8(_) => "Int8Property",
+      Property::Int16(_) => "Int16Property",
+      Property::Int(_) => "IntProperty",
+      Property::Int64(_) => "Int64Property",
+      Property::UInt16(_) => "UInt16Property",
+      Property::UInt32(_) => "UInt32Property",
+      Property::UInt64(_) => "UInt64Property",
+      Property::Bool(_) => "BoolProperty",
+      Property::Struct(_) => "StructProperty",
+      Property::Array(_) => "ArrayProperty",
+      Property::Float(_) => "FloatProperty",
+      Property::Double(_) => "DoubleProperty",
+      Property::MulticastInlineDelegate(_) => "MulticastInlineDelegateProperty",
+      Property::Str(_) => "StrProperty",
+      Property::Map(_) => "MapProperty",
+      Property::Set(_) => "SetProperty",
+      Property::Object(_) => "ObjectProperty",
+      Property::Enum { .. } => "EnumProperty",
+      Property::Name(_) => "NameProperty",
+      Property::Byte { .. } => "ByteProperty",
+      Property::Text(_) => "TextProperty",
+      Property::SoftObject(_) => "SoftObjectProperty",
+      Property::SoftClassPath(_) => "SoftClassPathProperty",
+      Property::LazyObject(_) => "LazyObjectProperty",
+      Property::Interface(_) => "InterfaceProperty",
+      Property::Unknown { type_name, .. } => type_name,
+    }
+  }
 }
 
 pub fn parse_property_map<
@@ -111,25 +629,237 @@ pub fn parse_property_map<
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], HashMap<String, Property>, E> {
-  context(
-    "property map",
-    map(
-      many_till(
-        context("property", |input| {
-          let (input, (property_name, property_type, _)) =
-            tuple((parse_string, parse_string, take(8usize)))(input)?;
-          let (input, property_value) = parse_property_value(property_type)(input)?;
-          Ok((input, (property_name, property_value)))
-        }),
-        verify(cut(parse_string), |s: &str| s == "None"),
+  ctx: ParseContext,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], PropertyMap, E> {
+  move |input| {
+    context("property map", |input| {
+      let (input, (properties, error)) = parse_properties(ctx.clone())(input)?;
+      match error {
+        Some(e) => Err(e),
+        None => Ok((input, properties.into_iter().collect())),
+      }
+    })(input)
+  }
+}
+
+/**
+ * The properties parsed from a property map in the order they were read, and
+ * the error which stopped the rest of it from being parsed, if any.
+ */
+pub type PartialPropertyMap<E> = (Vec<(String, TaggedProperty)>, Option<Err<E>>);
+
+/**
+ * Parses properties up to the "None" which ends a property map. Rather than
+ * failing, this stops at the first property which can't be parsed, and
+ * returns the properties before it along with the error, so that they can be
+ * kept as partial results.
+ */
+pub fn parse_properties<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  ctx: ParseContext,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], PartialPropertyMap<E>, E> {
+  let string = parse_string(ctx.options.max_string_length);
+  move |mut input| {
+    let mut properties = vec![];
+    loop {
+      match verify(cut(string), |s: &str| s == "None")(input) {
+        Ok((input, _)) => return Ok((input, (properties, None))),
+        Err(Err::Error(_)) => {}
+        Err(e) => return Ok((input, (properties, Some(e)))),
+      }
+      match parse_property(ctx.clone())(input) {
+        Ok((rest, property)) => {
+          properties.push(property);
+          input = rest;
+        }
+        Err(e) => return Ok((input, (properties, Some(e)))),
+      }
+    }
+  }
+}
+
+/**
+ * Parses a property tag and keeps the value which follows it as raw bytes,
+ * without trying to parse it. This is for getting past a property which
+ * failed to parse, using the size declared in its tag.
+ */
+pub fn skip_property<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], (String, TaggedProperty), E> {
+  move |input| {
+    let (start, header) = parse_property_tag(max_string_length)(input)?;
+    let (input, value) = parse_unknown_property(
+      header.property_type,
+      header.raw_data.to_vec(),
+      header.tag.size,
+    )(start)?;
+    Ok((
+      input,
+      (
+        header.name,
+        TaggedProperty {
+          tag: header.tag,
+          value,
+        },
       ),
-      |(v, _)| v.into_iter().collect(),
-    ),
-  )(input)
+    ))
+  }
+}
+
+/**
+ * Parses a property tag and the value which follows it. In lenient mode, a
+ * value which can't be parsed, or which doesn't take up the size declared in
+ * the tag when sizes are verified, is kept as raw bytes instead.
+ */
+fn parse_property<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  ctx: ParseContext,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], (String, TaggedProperty), E> {
+  move |input| {
+    context("property", |input| {
+      let (start, header) =
+        parse_property_tag(ctx.options.max_string_length)(input).map_err(|e| ctx.fail(e))?;
+      let ctx = ctx.with_segment(&header.name);
+      let size = header.tag.size;
+      ctx.trace(start, &header.property_type, size);
+      let parse_unknown = |message: String| {
+        ctx.recover();
+        ctx.warn(start, message);
+        parse_unknown_property(header.property_type.clone(), header.raw_data.to_vec(), size)(start)
+          .map_err(|e| ctx.fail(e))
+      };
+
+      let value =
+        parse_property_value(ctx.clone(), header.property_type.clone(), header.data, size)(start);
+      let (input, value) = match value {
+        Ok((input, value)) => match size_mismatch(&ctx, start, input, size) {
+          None => (input, value),
+          Some(message) if ctx.options.is_lenient() => parse_unknown(message)?,
+          Some(message) => {
+            return Err(ctx.fail(Err::Error(E::from_external_error(
+              start,
+              ErrorKind::Verify,
+              ParseFailure::new(ParseErrorKind::SizeMismatch, message),
+            ))))
+          }
+        },
+        Err(Err::Error(_) | Err::Failure(_)) if ctx.options.is_lenient() => {
+          parse_unknown(format!(
+            "Unable to parse {}, kept its {} bytes as they are",
+            header.property_type, size
+          ))?
+        }
+        Err(e) => return Err(ctx.fail(e)),
+      };
+
+      Ok((
+        input,
+        (
+          header.name,
+          TaggedProperty {
+            tag: header.tag,
+            value,
+          },
+        ),
+      ))
+    })(input)
+  }
+}
+
+/**
+ * Describes how the size of the value between `start` and `input` differs
+ * from the size declared for it, if sizes are being verified.
+ */
+fn size_mismatch(ctx: &ParseContext, start: &[u8], input: &[u8], size: u32) -> Option<String> {
+  let consumed = start.len() - input.len();
+  if !ctx.options.verify_sizes || consumed == size.to_usize() {
+    return None;
+  }
+  Some(format!(
+    "{} declared a size of {} bytes but {} were read",
+    ctx.path(),
+    size,
+    consumed
+  ))
+}
+
+/**
+ * The enum backing the bytes in an array, set or map is not recorded
+ * anywhere, so they are read as enum value names if `ParseOptions` gives an
+ * enum type for them. Otherwise the size of the container decides: `plain_size`
+ * is the size it would be if they were plain bytes, where that can be worked
+ * out, and any other size means they are enum value names.
+ */
+fn is_byte_labels(ctx: &ParseContext, plain_size: Option<u64>, size: u32) -> bool {
+  ctx.enum_type().is_some() || plain_size.is_some_and(|plain_size| plain_size != u64::from(size))
+}
+
+/**
+ * Peeks at a u32 ahead in the input without consuming it. Offsets and the
+ * sizes worked out from what is read are u64 so that no count in a save can
+ * overflow them.
+ */
+fn read_u32_at(input: &[u8], offset: u64) -> Option<u64> {
+  let offset = usize::try_from(offset).ok()?;
+  let bytes = input.get(offset..offset.checked_add(4)?)?;
+  Some(u64::from(u32::from_le_bytes([
+    bytes[0], bytes[1], bytes[2], bytes[3],
+  ])))
+}
+
+/**
+ * Parses a number of elements, giving each element parser a context with the
+ * index of the element at the end of the path, so that the path of the
+ * element is recorded if it fails to parse.
+ */
+fn count_indexed<'a, O, E: ParseError<&'a [u8]>>(
+  ctx: ParseContext,
+  element_count: usize,
+  mut element: impl FnMut(ParseContext, &'a [u8]) -> IResult<&'a [u8], O, E>,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<O>, E> {
+  move |mut input| {
+    // Every element takes up at least a byte, which stops a garbage count
+    // from allocating more than the input could hold
+    let mut values = Vec::with_capacity(element_count.min(input.len()));
+    for index in 0..element_count {
+      match element(ctx.with_index(index), input) {
+        Ok((rest, value)) => {
+          values.push(value);
+          input = rest;
+        }
+        Err(Err::Error(e)) => {
+          return Err(
+            ctx
+              .with_index(index)
+              .fail(Err::Error(E::append(input, ErrorKind::Count, e))),
+          )
+        }
+        Err(e) => return Err(ctx.with_index(index).fail(e)),
+      }
+    }
+    Ok((input, values))
+  }
 }
 
 fn parse_property_value<
@@ -138,26 +868,89 @@ fn parse_property_value<
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
+  ctx: ParseContext,
   property_type: String,
+  data: PropertyTagData,
+  size: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let max_string_length = ctx.options.max_string_length;
+  move |input| match &data {
+    // Bool properties have a size of 0, as their value is stored in the tag
+    PropertyTagData::Bool(b) => Ok((input, Property::Bool(*b))),
+    PropertyTagData::Enum(name) => parse_enum_property(name.to_owned(), max_string_length)(input),
+    PropertyTagData::Byte(name) => parse_byte_property(name.to_owned(), max_string_length)(input),
+    PropertyTagData::Struct { struct_type, .. } => {
+      parse_struct_property(ctx.clone(), struct_type.to_owned())(input)
+    }
+    PropertyTagData::Array(element_type) => {
+      parse_array_property(ctx.clone(), element_type.to_owned(), size)(input)
+    }
+    PropertyTagData::Set(element_type) => {
+      parse_set_property(ctx.clone(), element_type.to_owned(), size)(input)
+    }
+    PropertyTagData::Map {
+      key_type,
+      value_type,
+    } => parse_map_property(
+      ctx.clone(),
+      key_type.to_owned(),
+      value_type.to_owned(),
+      size,
+    )(input),
+    PropertyTagData::None => match property_type.as_str() {
+      "Int8Property" => parse_int8_property(input),
+      "Int16Property" => parse_int16_property(input),
+      "IntProperty" => parse_int_property(input),
+      "Int64Property" => parse_int64_property(input),
+      "UInt16Property" => parse_uint16_property(input),
+      "UInt32Property" => parse_uint32_property(input),
+      "UInt64Property" => parse_uint64_property(input),
+      "FloatProperty" => parse_float_property(input),
+      "DoubleProperty" => parse_double_property(input),
+      "StrProperty" => parse_str_property(max_string_length)(input),
+      "MulticastInlineDelegateProperty" => {
+        parse_multicast_inline_delegate_property(max_string_length)(input)
+      }
+      "ObjectProperty" => parse_object_property(max_string_length)(input),
+      "NameProperty" => parse_name_property(max_string_length)(input),
+      "TextProperty" => parse_text_property(max_string_length)(input),
+      "SoftObjectProperty" => parse_soft_object_property(max_string_length)(input),
+      "SoftClassPathProperty" => parse_soft_class_path_property(max_string_length)(input),
+      "LazyObjectProperty" => parse_lazy_object_property(input),
+      "InterfaceProperty" => parse_interface_property(max_string_length)(input),
+      _ => Err(Err::Error(E::from_external_error(
+        input,
+        ErrorKind::Fail,
+        ParseFailure::new(
+          ParseErrorKind::UnknownPropertyType,
+          format!("Unhandled property type {}", property_type),
+        ),
+      ))),
+    },
+  }
+}
+
+/**
+ * Reads the value of a property which couldn't otherwise be parsed as raw
+ * bytes, trusting the size declared in its tag.
+ */
+fn parse_unknown_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  type_name: String,
+  tag_data: Vec<u8>,
+  size: u32,
 ) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
-  move |input| match property_type.as_str() {
-    "IntProperty" => parse_int_property,
-    "UInt32Property" => parse_uint32_property,
-    "BoolProperty" => parse_bool_property,
-    "StructProperty" => parse_struct_property,
-    "ArrayProperty" => parse_array_property,
-    "FloatProperty" => parse_float_property,
-    "StrProperty" => parse_str_property,
-    "MulticastInlineDelegateProperty" => parse_multicast_inline_delegate_property,
-    "MapProperty" => parse_map_property,
-    "SetProperty" => parse_set_property,
-    "ObjectProperty" => parse_object_property,
-    "EnumProperty" => parse_enum_property,
-    "NameProperty" => parse_name_property,
-    _ => fail,
-  }(input)
+  move |input| {
+    context(
+      "unknown property",
+      map(take(size), |raw: &[u8]| Property::Unknown {
+        type_name: type_name.to_owned(),
+        tag_data: tag_data.to_owned(),
+        raw: raw.to_vec(),
+      }),
+    )(input)
+  }
 }
 
 fn parse_object_property<
@@ -165,80 +958,188 @@ fn parse_object_property<
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
-    + FromExternalError<&'a [u8], FromUtf8Error>,
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let string = parse_string(max_string_length);
+  context("object property", map(string, Property::Object))
+}
+
+/**
+ * Soft object paths are laid out in the structure:
+ *   [string | asset path name][string | sub path]
+ */
+fn parse_soft_object_path<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], SoftObjectPath, E> + Copy {
+  let string = parse_string(max_string_length);
+  move |input| {
+    context(
+      "soft object path",
+      map(
+        tuple((string, string)),
+        |(asset_path_name, sub_path_string)| SoftObjectPath {
+          asset_path_name,
+          sub_path_string,
+        },
+      ),
+    )(input)
+  }
+}
+
+fn parse_soft_object_property<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  context(
+    "soft object property",
+    map(
+      parse_soft_object_path(max_string_length),
+      Property::SoftObject,
+    ),
+  )
+}
+
+fn parse_soft_class_path_property<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  context(
+    "soft class path property",
+    map(
+      parse_soft_object_path(max_string_length),
+      Property::SoftClassPath,
+    ),
+  )
+}
+
+/**
+ * Lazy objects are stored as the unique GUID of the object they point to.
+ */
+fn parse_lazy_object_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
   input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
+) -> IResult<&'a [u8], Property, E> {
   context(
-    "object property",
-    map(preceded(take(1usize), parse_string), Property::Object),
+    "lazy object property",
+    map(parse_guid, Property::LazyObject),
   )(input)
 }
 
+fn parse_interface_property<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let string = parse_string(max_string_length);
+  context("interface property", map(string, Property::Interface))
+}
+
 fn parse_multicast_inline_delegate_property<
   'a,
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
-    + FromExternalError<&'a [u8], FromUtf8Error>,
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let string = parse_string(max_string_length);
   context(
     "multicast inline delegate property",
-    preceded(
-      take(1u8),
-      map(
-        length_count(
-          u32,
-          map(
-            tuple((parse_string, parse_string)),
-            |(object_path, function_name)| Delegate {
-              object_path,
-              function_name,
-            },
-          ),
-        ),
-        |delegates| Property::MulticastInlineDelegate(delegates),
+    map(
+      length_count(
+        u32,
+        map(tuple((string, string)), |(object_path, function_name)| {
+          Delegate {
+            object_path,
+            function_name,
+          }
+        }),
       ),
+      Property::MulticastInlineDelegate,
     ),
-  )(input)
+  )
+}
+
+fn parse_int8_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], Property, E> {
+  context("int8 property", map(i8, Property::Int8))(input)
+}
+
+fn parse_int16_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], Property, E> {
+  context("int16 property", map(i16, Property::Int16))(input)
 }
 
 fn parse_int_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
   input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context("int property", map(preceded(take(1u8), i32), Property::Int))(input)
+) -> IResult<&'a [u8], Property, E> {
+  context("int property", map(i32, Property::Int))(input)
+}
+
+fn parse_int64_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], Property, E> {
+  context("int64 property", map(i64, Property::Int64))(input)
+}
+
+fn parse_uint16_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], Property, E> {
+  context("uint16 property", map(u16, Property::UInt16))(input)
 }
 
 fn parse_uint32_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
   input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context(
-    "uint32 property",
-    map(preceded(take(1u8), u32), Property::UInt32),
-  )(input)
+) -> IResult<&'a [u8], Property, E> {
+  context("uint32 property", map(u32, Property::UInt32))(input)
 }
 
-fn parse_bool_property<
-  'a,
-  E: ParseError<&'a [u8]> + ContextError<&'a [u8]> + FromExternalError<&'a [u8], String>,
->(
+fn parse_uint64_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
   input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context(
-    "bool property",
-    map(preceded(take(1u8), parse_bool), Property::Bool),
-  )(input)
+) -> IResult<&'a [u8], Property, E> {
+  context("uint64 property", map(u64, Property::UInt64))(input)
 }
 
 fn parse_float_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
   input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context(
-    "float property",
-    map(preceded(take(1usize), f32), Property::Float),
-  )(input)
+) -> IResult<&'a [u8], Property, E> {
+  context("float property", map(f32, Property::Float))(input)
+}
+
+fn parse_double_property<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], Property, E> {
+  context("double property", map(f64, Property::Double))(input)
 }
 
 fn parse_str_property<
@@ -246,14 +1147,13 @@ fn parse_str_property<
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
-    + FromExternalError<&'a [u8], FromUtf8Error>,
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context(
-    "str property",
-    map(preceded(take(1usize), parse_string), Property::Str),
-  )(input)
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let string = parse_string(max_string_length);
+  context("str property", map(string, Property::Str))
 }
 
 fn parse_enum_property<
@@ -261,17 +1161,22 @@ fn parse_enum_property<
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
-    + FromExternalError<&'a [u8], FromUtf8Error>,
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context("enum property", |input| {
-    let (input, name) = parse_string(input)?;
-    let (input, _) = take(1usize)(input)?;
-    let (input, value) = parse_string(input)?;
-
-    Ok((input, Property::Enum { name, value }))
-  })(input)
+  name: String,
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let string = parse_string(max_string_length);
+  move |input| {
+    context(
+      "enum property",
+      map(string, |value| Property::Enum {
+        name: name.to_owned(),
+        value,
+      }),
+    )(input)
+  }
 }
 
 fn parse_name_property<
@@ -279,12 +1184,45 @@ fn parse_name_property<
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
-    + FromExternalError<&'a [u8], FromUtf8Error>,
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context(
-    "name property",
-    map(preceded(take(1usize), parse_string), Property::Name),
-  )(input)
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let string = parse_string(max_string_length);
+  context("name property", map(string, Property::Name))
+}
+
+/**
+ * Byte properties name the enum backing them in the tag, which is "None" for
+ * plain bytes. Enum backed bytes are stored as the name of the enum value.
+ */
+fn parse_byte_property<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  name: String,
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  let string = parse_string(max_string_length);
+  move |input| {
+    context("byte property", |input| {
+      let (input, value) = if name == "None" {
+        map(u8, BytePropertyValue::Byte)(input)?
+      } else {
+        map(string, BytePropertyValue::Label)(input)?
+      };
+      Ok((
+        input,
+        Property::Byte {
+          name: name.to_owned(),
+          value,
+        },
+      ))
+    })(input)
+  }
 }
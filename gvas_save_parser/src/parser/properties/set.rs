@@ -1,43 +1,156 @@
-use super::{Property, SetPropertyValue};
-use crate::parser::primitives::{parse_guid, parse_string};
+use super::{
+  count_indexed, is_byte_labels, parse_property_map, r#struct::parse_struct_value, read_u32_at,
+  BytePropertyValue, Property, SetProperty, SetPropertyValue, StructPropertyValue,
+};
+use crate::parser::{
+  context::ParseContext,
+  error::{ParseErrorKind, ParseFailure},
+  primitives::parse_string,
+};
 use nom::{
-  bytes::complete::take,
   combinator::map,
   error::{context, ContextError, ErrorKind, FromExternalError, ParseError},
-  multi::count,
-  number::complete::le_u32 as u32,
+  number::complete::{
+    le_f32 as f32, le_f64 as f64, le_i16 as i16, le_i32 as i32, le_i64 as i64, le_i8 as i8,
+    le_u16 as u16, le_u32 as u32, le_u64 as u64, le_u8 as u8,
+  },
   Err, IResult, ToUsize,
 };
 use std::string::{FromUtf16Error, FromUtf8Error};
 
+/**
+ * Sets are laid out in the structure:
+ *   [u32 | elements to remove count][elements to remove]
+ *   [u32 | element count][elements]
+ * where the elements to remove are only used when the set overrides a default
+ * value, and are almost always empty in saves.
+ */
 pub fn parse_set_property<
   'a,
   E: ParseError<&'a [u8]>
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context("set property", |input| {
-    let (input, property_type) = parse_string(input)?;
-    let (input, _) = take(5u8)(input)?;
-    let (input, num_properties) = u32(input)?;
+  ctx: ParseContext,
+  property_type: String,
+  size: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  move |input| {
+    context("set property", |input| {
+      let struct_type = match property_type.as_str() {
+        "StructProperty" => ctx
+          .struct_type()
+          .or_else(|| guess_set_struct_type(input, size)),
+        _ => None,
+      };
+      let byte_labels =
+        property_type == "ByteProperty" && is_byte_labels(&ctx, plain_byte_set_size(input), size);
+
+      let (input, elements_to_remove_count) = u32(input)?;
+      let (input, elements_to_remove) = context(
+        "set elements to remove",
+        count_indexed(
+          ctx.clone(),
+          elements_to_remove_count.to_usize(),
+          |ctx, input| parse_set_element(ctx, &property_type, struct_type, byte_labels)(input),
+        ),
+      )(input)?;
+      let (input, element_count) = u32(input)?;
+      let (input, elements) =
+        count_indexed(ctx.clone(), element_count.to_usize(), |ctx, input| {
+          parse_set_element(ctx, &property_type, struct_type, byte_labels)(input)
+        })(input)?;
+
+      Ok((
+        input,
+        Property::Set(SetProperty {
+          element_type: property_type.to_owned(),
+          elements_to_remove,
+          elements,
+        }),
+      ))
+    })(input)
+  }
+}
+
+/**
+ * The struct type of a set of structs is not recorded anywhere. Sets of GUIDs
+ * are by far the most common, and take up exactly 16 bytes per element, so
+ * the size of the set is used to tell them apart from sets of any other
+ * struct, which have no known type and are read as a property map.
+ */
+fn guess_set_struct_type(input: &[u8], size: u32) -> Option<&'static str> {
+  let guid_set_size = read_u32_at(input, 0).and_then(|removed| {
+    let count = read_u32_at(input, 4 + 16 * removed)?;
+    Some(8 + 16 * (removed + count))
+  });
+
+  (guid_set_size == Some(u64::from(size))).then_some("Guid")
+}
 
-    map(
-      count(
-        move |input| match property_type.as_str() {
-          "StructProperty" => map(parse_guid, SetPropertyValue::Struct)(input),
-          e => Err(Err::Error(E::from_external_error(
-            input,
-            ErrorKind::Fail,
-            format!("Unhandled map property value type {}", e),
-          ))),
-        },
-        num_properties.to_usize(),
-      ),
-      |v| Property::Set(v.into_iter().collect()),
-    )(input)
-  })(input)
+/**
+ * The size of a set of bytes if they are plain bytes, which take up exactly
+ * one byte each.
+ */
+fn plain_byte_set_size(input: &[u8]) -> Option<u64> {
+  let removed = read_u32_at(input, 0)?;
+  let count = read_u32_at(input, 4 + removed)?;
+  Some(8 + removed + count)
+}
+
+fn parse_set_element<
+  'a,
+  't,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  ctx: ParseContext,
+  property_type: &'t str,
+  struct_type: Option<&'t str>,
+  byte_labels: bool,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], SetPropertyValue, E> + 't {
+  let max_string_length = ctx.options.max_string_length;
+  move |input| {
+    context("set element", |input| match (property_type, struct_type) {
+      ("StructProperty", Some(struct_type)) => map(
+        parse_struct_value(ctx.clone(), struct_type.to_owned()),
+        SetPropertyValue::Struct,
+      )(input),
+      ("StructProperty", None) => map(parse_property_map(ctx.clone()), |value| {
+        SetPropertyValue::Struct(StructPropertyValue::Untyped(value))
+      })(input),
+      ("Int8Property", _) => map(i8, SetPropertyValue::Int8)(input),
+      ("Int16Property", _) => map(i16, SetPropertyValue::Int16)(input),
+      ("IntProperty", _) => map(i32, SetPropertyValue::Int)(input),
+      ("Int64Property", _) => map(i64, SetPropertyValue::Int64)(input),
+      ("UInt16Property", _) => map(u16, SetPropertyValue::UInt16)(input),
+      ("UInt32Property", _) => map(u32, SetPropertyValue::UInt32)(input),
+      ("UInt64Property", _) => map(u64, SetPropertyValue::UInt64)(input),
+      ("FloatProperty", _) => map(f32, SetPropertyValue::Float)(input),
+      ("DoubleProperty", _) => map(f64, SetPropertyValue::Double)(input),
+      ("ByteProperty", _) if byte_labels => map(parse_string(max_string_length), |s| {
+        SetPropertyValue::Byte(BytePropertyValue::Label(s))
+      })(input),
+      ("ByteProperty", _) => map(u8, |b| SetPropertyValue::Byte(BytePropertyValue::Byte(b)))(input),
+      ("StrProperty", _) => map(parse_string(max_string_length), SetPropertyValue::Str)(input),
+      ("NameProperty", _) => map(parse_string(max_string_length), SetPropertyValue::Name)(input),
+      ("EnumProperty", _) => map(parse_string(max_string_length), SetPropertyValue::Enum)(input),
+      ("ObjectProperty", _) => {
+        map(parse_string(max_string_length), SetPropertyValue::Object)(input)
+      }
+      (e, _) => Err(Err::Error(E::from_external_error(
+        input,
+        ErrorKind::Fail,
+        ParseFailure::new(
+          ParseErrorKind::UnknownPropertyType,
+          format!("Unhandled set property element type {}", e),
+        ),
+      ))),
+    })(input)
+  }
 }
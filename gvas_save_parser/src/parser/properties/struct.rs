@@ -1,10 +1,15 @@
-use super::{parse_property_map, Property};
+use super::{
+  math::{
+    parse_box, parse_color, parse_int_point, parse_int_vector, parse_linear_color, parse_quat,
+    parse_rotator, parse_transform, parse_vector, parse_vector_2d, parse_vector_4,
+  },
+  parse_property_map, Property,
+};
 use crate::parser::{
-  primitives::{parse_guid, parse_string},
+  context::ParseContext, error::ParseFailure, primitives::parse_guid,
   properties::StructPropertyValue,
 };
 use nom::{
-  bytes::complete::take,
   combinator::map,
   error::{context, ContextError, FromExternalError, ParseError},
   number::complete::le_i64 as i64,
@@ -18,16 +23,20 @@ pub fn parse_struct_property<
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
-  input: &'a [u8],
-) -> IResult<&[u8], Property, E> {
-  context("struct property", |input| {
-    let (input, struct_type) = parse_string(input)?;
-    let (input, _) = take(17u8)(input)?;
-    let (input, struct_value) = parse_struct_value(struct_type)(input)?;
-    Ok((input, Property::Struct(struct_value)))
-  })(input)
+  ctx: ParseContext,
+  struct_type: String,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  move |input| {
+    context(
+      "struct property",
+      map(
+        parse_struct_value(ctx.clone(), struct_type.to_owned()),
+        Property::Struct,
+      ),
+    )(input)
+  }
 }
 
 pub fn parse_struct_value<
@@ -36,16 +45,31 @@ pub fn parse_struct_value<
     + ContextError<&'a [u8]>
     + FromExternalError<&'a [u8], FromUtf16Error>
     + FromExternalError<&'a [u8], FromUtf8Error>
-    + FromExternalError<&'a [u8], String>,
+    + FromExternalError<&'a [u8], ParseFailure>,
 >(
+  ctx: ParseContext,
   struct_type: String,
 ) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], StructPropertyValue, E> {
   move |input| match struct_type.as_str() {
     "Guid" => map(parse_guid, StructPropertyValue::Guid)(input),
     "DateTime" => map(i64, StructPropertyValue::DateTime)(input),
-    _ => map(parse_property_map, |value| StructPropertyValue::Foreign {
-      name: struct_type.to_owned(),
-      value,
+    "Vector" => map(parse_vector(ctx.version), StructPropertyValue::Vector)(input),
+    "Vector2D" => map(parse_vector_2d(ctx.version), StructPropertyValue::Vector2D)(input),
+    "Vector4" => map(parse_vector_4(ctx.version), StructPropertyValue::Vector4)(input),
+    "Rotator" => map(parse_rotator(ctx.version), StructPropertyValue::Rotator)(input),
+    "Quat" => map(parse_quat(ctx.version), StructPropertyValue::Quat)(input),
+    "LinearColor" => map(parse_linear_color, StructPropertyValue::LinearColor)(input),
+    "Color" => map(parse_color, StructPropertyValue::Color)(input),
+    "IntPoint" => map(parse_int_point, StructPropertyValue::IntPoint)(input),
+    "IntVector" => map(parse_int_vector, StructPropertyValue::IntVector)(input),
+    "Box" => map(parse_box(ctx.version), StructPropertyValue::Box)(input),
+    "Timespan" => map(i64, StructPropertyValue::Timespan)(input),
+    "Transform" => parse_transform(ctx.clone())(input),
+    _ => map(parse_property_map(ctx.clone()), |value| {
+      StructPropertyValue::Foreign {
+        name: struct_type.to_owned(),
+        value,
+      }
     })(input),
   }
 }
@@ -0,0 +1,144 @@
+use super::PropertyTag;
+use crate::parser::{
+  error::ParseFailure,
+  primitives::{parse_bool, parse_guid, parse_string, Guid},
+};
+use nom::{
+  combinator::{consumed, map, success},
+  error::{context, ContextError, FromExternalError, ParseError},
+  number::complete::le_u32 as u32,
+  sequence::tuple,
+  IResult,
+};
+use std::string::{FromUtf16Error, FromUtf8Error};
+
+/**
+ * The parts of a property tag which depend on the type of the property. These
+ * are needed to parse the value, after which they are available on the value
+ * itself, so they are not kept on the `PropertyTag`.
+ */
+#[derive(Clone)]
+pub enum PropertyTagData {
+  None,
+  Bool(bool),
+  Enum(String),
+  Byte(String),
+  Struct {
+    struct_type: String,
+    guid: Guid,
+  },
+  Array(String),
+  Set(String),
+  Map {
+    key_type: String,
+    value_type: String,
+  },
+}
+
+pub struct PropertyHeader<'a> {
+  pub name: String,
+  pub property_type: String,
+  pub data: PropertyTagData,
+  /**
+   * The bytes the type specific data was read from, for keeping properties
+   * which can't be parsed as they are.
+   */
+  pub raw_data: &'a [u8],
+  pub tag: PropertyTag,
+}
+
+/**
+ * Property tags are laid out in the structure:
+ *   [string | name][string | type][u32 | size][u32 | array index][data][u8 | has guid]
+ * followed by a 16-byte property guid if the has guid flag is set. The size
+ * is that of the value which follows the tag.
+ */
+pub fn parse_property_tag<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], PropertyHeader<'a>, E> {
+  let string = parse_string(max_string_length);
+  context("property tag", move |input| {
+    let (input, (name, property_type, size, array_index)) =
+      tuple((string, string, u32, u32))(input)?;
+    let (input, (raw_data, data)) =
+      consumed(parse_property_tag_data(&property_type, max_string_length))(input)?;
+    let (input, guid) = parse_property_guid(input)?;
+
+    let struct_guid = match &data {
+      PropertyTagData::Struct { guid, .. } => Some(*guid),
+      _ => None,
+    };
+
+    Ok((
+      input,
+      PropertyHeader {
+        name,
+        property_type,
+        data,
+        raw_data,
+        tag: PropertyTag {
+          size,
+          array_index,
+          struct_guid,
+          guid,
+        },
+      },
+    ))
+  })
+}
+
+fn parse_property_tag_data<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  property_type: &str,
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], PropertyTagData, E> + '_ {
+  move |input| {
+    let string = parse_string(max_string_length);
+    match property_type {
+      "BoolProperty" => map(parse_bool, PropertyTagData::Bool)(input),
+      "EnumProperty" => map(string, PropertyTagData::Enum)(input),
+      "ByteProperty" => map(string, PropertyTagData::Byte)(input),
+      "StructProperty" => map(tuple((string, parse_guid)), |(struct_type, guid)| {
+        PropertyTagData::Struct { struct_type, guid }
+      })(input),
+      "ArrayProperty" => map(string, PropertyTagData::Array)(input),
+      "SetProperty" => map(string, PropertyTagData::Set)(input),
+      "MapProperty" => map(tuple((string, string)), |(key_type, value_type)| {
+        PropertyTagData::Map {
+          key_type,
+          value_type,
+        }
+      })(input),
+      _ => success(PropertyTagData::None)(input),
+    }
+  }
+}
+
+fn parse_property_guid<
+  'a,
+  E: ParseError<&'a [u8]> + ContextError<&'a [u8]> + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], Option<Guid>, E> {
+  context("property guid", |input| {
+    let (input, has_guid) = parse_bool(input)?;
+    if has_guid {
+      map(parse_guid, Some)(input)
+    } else {
+      Ok((input, None))
+    }
+  })(input)
+}
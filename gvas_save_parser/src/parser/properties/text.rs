@@ -0,0 +1,406 @@
+use super::Property;
+use crate::parser::{
+  error::{ParseErrorKind, ParseFailure},
+  primitives::parse_string,
+};
+use nom::{
+  combinator::{map, map_res},
+  error::{context, ContextError, ErrorKind, FromExternalError, ParseError},
+  multi::length_count,
+  number::complete::{
+    le_f32 as f32, le_f64 as f64, le_i32 as i32, le_i64 as i64, le_i8 as i8, le_u32 as u32,
+    le_u64 as u64, le_u8 as u8,
+  },
+  sequence::tuple,
+  Err, IResult,
+};
+use serde::Serialize;
+use std::string::{FromUtf16Error, FromUtf8Error};
+
+/**
+ * Localized text, which is stored as the history of how the text was built
+ * rather than as the final string.
+ */
+#[derive(Debug, Serialize)]
+pub struct FText {
+  pub flags: u32,
+  pub history: TextHistory,
+}
+
+#[derive(Debug, Serialize)]
+pub enum TextHistory {
+  None {
+    culture_invariant_string: Option<String>,
+  },
+  Base {
+    namespace: String,
+    key: String,
+    source_string: String,
+  },
+  NamedFormat {
+    source_format: Box<FText>,
+    arguments: Vec<(String, FormatArgumentValue)>,
+  },
+  OrderedFormat {
+    source_format: Box<FText>,
+    arguments: Vec<FormatArgumentValue>,
+  },
+  ArgumentFormat {
+    source_format: Box<FText>,
+    arguments: Vec<(String, FormatArgumentValue)>,
+  },
+  AsNumber {
+    source_value: FormatArgumentValue,
+    format_options: Option<NumberFormattingOptions>,
+    target_culture: String,
+  },
+  AsPercent {
+    source_value: FormatArgumentValue,
+    format_options: Option<NumberFormattingOptions>,
+    target_culture: String,
+  },
+  AsCurrency {
+    currency_code: String,
+    source_value: FormatArgumentValue,
+    format_options: Option<NumberFormattingOptions>,
+    target_culture: String,
+  },
+  AsDate {
+    source_date_time: i64,
+    date_style: i8,
+    time_zone: String,
+    target_culture: String,
+  },
+  AsTime {
+    source_date_time: i64,
+    time_style: i8,
+    time_zone: String,
+    target_culture: String,
+  },
+  AsDateTime {
+    source_date_time: i64,
+    date_style: i8,
+    time_style: i8,
+    time_zone: String,
+    target_culture: String,
+  },
+  Transform {
+    source_text: Box<FText>,
+    transform_type: u8,
+  },
+  StringTableEntry {
+    table_id: String,
+    key: String,
+  },
+}
+
+impl TextHistory {
+  /**
+   * The history type byte written ahead of the history in the save.
+   */
+  pub fn history_type(&self) -> i8 {
+    match self {
+      TextHistory::None { .. } => -1,
+      TextHistory::Base { .. } => 0,
+      TextHistory::NamedFormat { .. } => 1,
+      TextHistory::OrderedFormat { .. } => 2,
+      TextHistory::ArgumentFormat { .. } => 3,
+      TextHistory::AsNumber { .. } => 4,
+      TextHistory::AsPercent { .. } => 5,
+      TextHistory::AsCurrency { .. } => 6,
+      TextHistory::AsDate { .. } => 7,
+      TextHistory::AsTime { .. } => 8,
+      TextHistory::AsDateTime { .. } => 9,
+      TextHistory::Transform { .. } => 10,
+      TextHistory::StringTableEntry { .. } => 11,
+    }
+  }
+}
+
+#[derive(Debug, Serialize)]
+pub enum FormatArgumentValue {
+  Int(i64),
+  UInt(u64),
+  Float(f32),
+  Double(f64),
+  Text(Box<FText>),
+  Gender(u8),
+}
+
+#[derive(Debug, Serialize)]
+pub struct NumberFormattingOptions {
+  pub always_sign: bool,
+  pub use_grouping: bool,
+  pub rounding_mode: i8,
+  pub minimum_integral_digits: i32,
+  pub maximum_integral_digits: i32,
+  pub minimum_fractional_digits: i32,
+  pub maximum_fractional_digits: i32,
+}
+
+pub fn parse_text_property<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Property, E> {
+  context(
+    "text property",
+    map(parse_text(max_string_length), Property::Text),
+  )
+}
+
+/**
+ * Text is laid out in the structure:
+ *   [u32 | flags][i8 | history type][history]
+ * where the layout of the history depends on its type. Formatted histories
+ * contain further text, so this is recursive.
+ */
+pub fn parse_text<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  max_string_length: u32,
+) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], FText, E> + Copy {
+  move |input| {
+    context("text", |input| {
+      let (input, flags) = u32(input)?;
+      let (input, history) = parse_text_history(input, max_string_length)?;
+      Ok((input, FText { flags, history }))
+    })(input)
+  }
+}
+
+fn parse_text_history<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  input: &'a [u8],
+  max_string_length: u32,
+) -> IResult<&'a [u8], TextHistory, E> {
+  let string = parse_string(max_string_length);
+  let text = parse_text(max_string_length);
+  let argument = |input| parse_format_argument_value(input, max_string_length);
+  let (input, history_type) = i8(input)?;
+  context("text history", move |input| match history_type {
+    -1 => {
+      let (input, has_culture_invariant_string) = parse_bool32(input)?;
+      let (input, culture_invariant_string) = if has_culture_invariant_string {
+        map(string, Some)(input)?
+      } else {
+        (input, None)
+      };
+      Ok((
+        input,
+        TextHistory::None {
+          culture_invariant_string,
+        },
+      ))
+    }
+    0 => map(
+      tuple((string, string, string)),
+      |(namespace, key, source_string)| TextHistory::Base {
+        namespace,
+        key,
+        source_string,
+      },
+    )(input),
+    1 => map(
+      tuple((text, length_count(u32, tuple((string, argument))))),
+      |(source_format, arguments)| TextHistory::NamedFormat {
+        source_format: Box::new(source_format),
+        arguments,
+      },
+    )(input),
+    2 => map(
+      tuple((text, length_count(u32, argument))),
+      |(source_format, arguments)| TextHistory::OrderedFormat {
+        source_format: Box::new(source_format),
+        arguments,
+      },
+    )(input),
+    3 => map(
+      tuple((text, length_count(u32, tuple((string, argument))))),
+      |(source_format, arguments)| TextHistory::ArgumentFormat {
+        source_format: Box::new(source_format),
+        arguments,
+      },
+    )(input),
+    4 => map(
+      tuple((argument, parse_number_formatting_options, string)),
+      |(source_value, format_options, target_culture)| TextHistory::AsNumber {
+        source_value,
+        format_options,
+        target_culture,
+      },
+    )(input),
+    5 => map(
+      tuple((argument, parse_number_formatting_options, string)),
+      |(source_value, format_options, target_culture)| TextHistory::AsPercent {
+        source_value,
+        format_options,
+        target_culture,
+      },
+    )(input),
+    6 => map(
+      tuple((string, argument, parse_number_formatting_options, string)),
+      |(currency_code, source_value, format_options, target_culture)| TextHistory::AsCurrency {
+        currency_code,
+        source_value,
+        format_options,
+        target_culture,
+      },
+    )(input),
+    7 => map(
+      tuple((i64, i8, string, string)),
+      |(source_date_time, date_style, time_zone, target_culture)| TextHistory::AsDate {
+        source_date_time,
+        date_style,
+        time_zone,
+        target_culture,
+      },
+    )(input),
+    8 => map(
+      tuple((i64, i8, string, string)),
+      |(source_date_time, time_style, time_zone, target_culture)| TextHistory::AsTime {
+        source_date_time,
+        time_style,
+        time_zone,
+        target_culture,
+      },
+    )(input),
+    9 => map(
+      tuple((i64, i8, i8, string, string)),
+      |(source_date_time, date_style, time_style, time_zone, target_culture)| {
+        TextHistory::AsDateTime {
+          source_date_time,
+          date_style,
+          time_style,
+          time_zone,
+          target_culture,
+        }
+      },
+    )(input),
+    10 => map(tuple((text, u8)), |(source_text, transform_type)| {
+      TextHistory::Transform {
+        source_text: Box::new(source_text),
+        transform_type,
+      }
+    })(input),
+    11 => map(tuple((string, string)), |(table_id, key)| {
+      TextHistory::StringTableEntry { table_id, key }
+    })(input),
+    t => Err(Err::Error(E::from_external_error(
+      input,
+      ErrorKind::Fail,
+      ParseFailure::new(
+        ParseErrorKind::UnknownTextHistoryType,
+        format!("Unhandled text history type {}", t),
+      ),
+    ))),
+  })(input)
+}
+
+fn parse_format_argument_value<
+  'a,
+  E: ParseError<&'a [u8]>
+    + ContextError<&'a [u8]>
+    + FromExternalError<&'a [u8], FromUtf16Error>
+    + FromExternalError<&'a [u8], FromUtf8Error>
+    + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  input: &'a [u8],
+  max_string_length: u32,
+) -> IResult<&'a [u8], FormatArgumentValue, E> {
+  let (input, argument_type) = i8(input)?;
+  context("format argument value", move |input| match argument_type {
+    0 => map(i64, FormatArgumentValue::Int)(input),
+    1 => map(u64, FormatArgumentValue::UInt)(input),
+    2 => map(f32, FormatArgumentValue::Float)(input),
+    3 => map(f64, FormatArgumentValue::Double)(input),
+    4 => map(parse_text(max_string_length), |t| {
+      FormatArgumentValue::Text(Box::new(t))
+    })(input),
+    5 => map(u8, FormatArgumentValue::Gender)(input),
+    t => Err(Err::Error(E::from_external_error(
+      input,
+      ErrorKind::Fail,
+      ParseFailure::new(
+        ParseErrorKind::UnknownTextArgumentType,
+        format!("Unhandled format argument type {}", t),
+      ),
+    ))),
+  })(input)
+}
+
+fn parse_number_formatting_options<
+  'a,
+  E: ParseError<&'a [u8]> + ContextError<&'a [u8]> + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], Option<NumberFormattingOptions>, E> {
+  let (input, has_format_options) = parse_bool32(input)?;
+  if !has_format_options {
+    return Ok((input, None));
+  }
+
+  context(
+    "number formatting options",
+    map(
+      tuple((parse_bool32, parse_bool32, i8, i32, i32, i32, i32)),
+      |(
+        always_sign,
+        use_grouping,
+        rounding_mode,
+        minimum_integral_digits,
+        maximum_integral_digits,
+        minimum_fractional_digits,
+        maximum_fractional_digits,
+      )| {
+        Some(NumberFormattingOptions {
+          always_sign,
+          use_grouping,
+          rounding_mode,
+          minimum_integral_digits,
+          maximum_integral_digits,
+          minimum_fractional_digits,
+          maximum_fractional_digits,
+        })
+      },
+    ),
+  )(input)
+}
+
+/**
+ * Outside of property tags, booleans in text are stored as full u32s.
+ */
+fn parse_bool32<
+  'a,
+  E: ParseError<&'a [u8]> + ContextError<&'a [u8]> + FromExternalError<&'a [u8], ParseFailure>,
+>(
+  input: &'a [u8],
+) -> IResult<&'a [u8], bool, E> {
+  context(
+    "bool32",
+    map_res(u32, |i| match i {
+      0 => Ok(false),
+      1 => Ok(true),
+      n => Err(ParseFailure::new(
+        ParseErrorKind::InvalidBool,
+        format!("Invalid boolean value {}", n),
+      )),
+    }),
+  )(input)
+}
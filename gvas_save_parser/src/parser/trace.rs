@@ -0,0 +1,15 @@
+use super::PropertyPath;
+use serde::Serialize;
+
+/**
+ * A property the parser came across, recorded in the order they were found
+ * when `ParseOptions::trace` is on. The offset is that of the property's
+ * value, just after its tag.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseTraceEntry {
+  pub offset: usize,
+  pub path: PropertyPath,
+  pub property_type: String,
+  pub size: u32,
+}
@@ -0,0 +1,16 @@
+use super::PropertyPath;
+use serde::Serialize;
+
+/**
+ * Something in the save which was not understood, but which did not stop it
+ * from being parsed in lenient mode. The offset is from the start of the
+ * save, and the path is that of the property the problem was found in, in
+ * the same format as the paths in `ParseOptions` but with the index of each
+ * element along the way, e.g. `SchematicSave.ForceUnlocked[3]`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseWarning {
+  pub offset: usize,
+  pub path: PropertyPath,
+  pub message: String,
+}
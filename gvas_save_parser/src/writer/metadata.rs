@@ -0,0 +1,24 @@
+use super::primitives::{write_guid, write_string, write_u16, write_u32};
+use crate::parser::Metadata;
+
+pub fn write_metadata(out: &mut Vec<u8>, metadata: &Metadata) {
+  write_u32(out, metadata.save_version);
+  write_u32(out, metadata.package_version);
+  if let Some(package_version_ue5) = metadata.package_version_ue5 {
+    write_u32(out, package_version_ue5);
+  }
+  write_u16(out, metadata.engine_major);
+  write_u16(out, metadata.engine_minor);
+  write_u16(out, metadata.engine_patch);
+  write_u32(out, metadata.engine_build);
+  write_string(out, &metadata.engine_build_id);
+  if let Some(custom_format_version) = metadata.custom_format_version {
+    write_u32(out, custom_format_version);
+    write_u32(out, metadata.custom_format_data.len() as u32);
+    for (guid, version) in &metadata.custom_format_data {
+      write_guid(out, guid);
+      write_u32(out, *version);
+    }
+  }
+  write_string(out, &metadata.save_game_type);
+}
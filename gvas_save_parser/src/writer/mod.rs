@@ -0,0 +1,23 @@
+mod metadata;
+mod primitives;
+mod properties;
+
+use crate::parser::SaveFile;
+use metadata::write_metadata;
+use properties::write_property_map;
+
+/**
+ * Writes a save file in the same layout `root` parses it from. Sizes stored in
+ * the property tags are recomputed from the values being written rather than
+ * trusted from the model.
+ */
+pub fn write_root(out: &mut Vec<u8>, save_file: &SaveFile) {
+  out.extend_from_slice(b"GVAS");
+  write_metadata(out, &save_file.metadata);
+  write_property_map(
+    out,
+    &save_file.properties,
+    save_file.metadata.archive_version(),
+  );
+  out.extend_from_slice(&save_file.trailer);
+}
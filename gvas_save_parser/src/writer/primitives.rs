@@ -0,0 +1,76 @@
+use crate::parser::{Guid, SoftObjectPath};
+
+/**
+ * Strings are written in the same layout `parse_string` reads them in. Pure
+ * ASCII strings are written as single-byte strings, anything else is written
+ * as a wide string, with a trailing null-terminator in both cases. Empty
+ * strings are written as a bare length of 0.
+ *
+ * The encoding a string was read in is not kept, so a save which stores an
+ * ASCII string as a wide string, or an empty string as a lone
+ * null-terminator, is written back in this form instead. The engine writes
+ * strings the same way, so saves it wrote still round-trip byte for byte.
+ */
+pub fn write_string(out: &mut Vec<u8>, s: &str) {
+  if s.is_empty() {
+    write_i32(out, 0);
+  } else if s.is_ascii() {
+    write_i32(out, s.len() as i32 + 1);
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+  } else {
+    let wide = s.encode_utf16().collect::<Vec<_>>();
+    write_i32(out, -(wide.len() as i32 + 1));
+    wide.into_iter().for_each(|c| write_u16(out, c));
+    write_u16(out, 0);
+  }
+}
+
+pub fn write_guid(out: &mut Vec<u8>, guid: &Guid) {
+  guid.0.into_iter().for_each(|i| write_u32(out, i));
+}
+
+pub fn write_soft_object_path(out: &mut Vec<u8>, path: &SoftObjectPath) {
+  write_string(out, &path.asset_path_name);
+  write_string(out, &path.sub_path_string);
+}
+
+pub fn write_bool(out: &mut Vec<u8>, b: bool) {
+  out.push(b as u8);
+}
+
+pub fn write_i8(out: &mut Vec<u8>, i: i8) {
+  out.extend_from_slice(&i.to_le_bytes());
+}
+
+pub fn write_i16(out: &mut Vec<u8>, i: i16) {
+  out.extend_from_slice(&i.to_le_bytes());
+}
+
+pub fn write_u16(out: &mut Vec<u8>, i: u16) {
+  out.extend_from_slice(&i.to_le_bytes());
+}
+
+pub fn write_i32(out: &mut Vec<u8>, i: i32) {
+  out.extend_from_slice(&i.to_le_bytes());
+}
+
+pub fn write_u32(out: &mut Vec<u8>, i: u32) {
+  out.extend_from_slice(&i.to_le_bytes());
+}
+
+pub fn write_i64(out: &mut Vec<u8>, i: i64) {
+  out.extend_from_slice(&i.to_le_bytes());
+}
+
+pub fn write_u64(out: &mut Vec<u8>, i: u64) {
+  out.extend_from_slice(&i.to_le_bytes());
+}
+
+pub fn write_f32(out: &mut Vec<u8>, f: f32) {
+  out.extend_from_slice(&f.to_le_bytes());
+}
+
+pub fn write_f64(out: &mut Vec<u8>, f: f64) {
+  out.extend_from_slice(&f.to_le_bytes());
+}
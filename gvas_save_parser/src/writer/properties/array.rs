@@ -0,0 +1,69 @@
+use super::{
+  r#struct::write_struct_value, text::write_text, write_byte_value, write_property_guid,
+};
+use crate::{
+  parser::{ArchiveVersion, ArrayProperty, ArrayPropertyValue, ArrayPropertyValues},
+  writer::primitives::{
+    write_bool, write_f32, write_f64, write_guid, write_i16, write_i32, write_i64, write_i8,
+    write_soft_object_path, write_string, write_u16, write_u32, write_u64,
+  },
+};
+
+pub fn write_array_property(
+  tag: &mut Vec<u8>,
+  value: &mut Vec<u8>,
+  array: &ArrayProperty,
+  version: ArchiveVersion,
+) {
+  write_string(tag, &array.element_type);
+
+  write_u32(value, array.values.len() as u32);
+
+  let mut values = vec![];
+  match &array.values {
+    ArrayPropertyValues::Bytes(bytes) => values.extend_from_slice(bytes),
+    ArrayPropertyValues::Elements(elements) => elements
+      .iter()
+      .for_each(|v| write_array_element(&mut values, v, version)),
+  }
+
+  // Struct arrays repeat a full property tag, sized to all of the elements
+  if let Some(struct_tag) = &array.struct_tag {
+    write_string(value, &struct_tag.name);
+    write_string(value, "StructProperty");
+    write_u32(value, values.len() as u32);
+    write_u32(value, struct_tag.tag.array_index);
+    write_string(value, &struct_tag.struct_type);
+    write_guid(value, &struct_tag.tag.struct_guid.unwrap_or_default());
+    write_property_guid(value, &struct_tag.tag.guid);
+  }
+
+  value.extend_from_slice(&values);
+}
+
+fn write_array_element(out: &mut Vec<u8>, v: &ArrayPropertyValue, version: ArchiveVersion) {
+  match v {
+    ArrayPropertyValue::Int8(i) => write_i8(out, *i),
+    ArrayPropertyValue::Int16(i) => write_i16(out, *i),
+    ArrayPropertyValue::Int(i) => write_i32(out, *i),
+    ArrayPropertyValue::Int64(i) => write_i64(out, *i),
+    ArrayPropertyValue::UInt16(i) => write_u16(out, *i),
+    ArrayPropertyValue::UInt32(i) => write_u32(out, *i),
+    ArrayPropertyValue::UInt64(i) => write_u64(out, *i),
+    ArrayPropertyValue::Float(i) => write_f32(out, *i),
+    ArrayPropertyValue::Double(i) => write_f64(out, *i),
+    ArrayPropertyValue::Bool(b) => write_bool(out, *b),
+    ArrayPropertyValue::Str(s)
+    | ArrayPropertyValue::Name(s)
+    | ArrayPropertyValue::Enum(s)
+    | ArrayPropertyValue::Object(s) => write_string(out, s),
+    ArrayPropertyValue::Text(t) => write_text(out, t),
+    ArrayPropertyValue::Struct(s) => write_struct_value(out, s, version),
+    ArrayPropertyValue::Byte(b) => write_byte_value(out, b),
+    ArrayPropertyValue::SoftObject(p) | ArrayPropertyValue::SoftClassPath(p) => {
+      write_soft_object_path(out, p)
+    }
+    ArrayPropertyValue::LazyObject(g) => write_guid(out, g),
+    ArrayPropertyValue::Interface(s) => write_string(out, s),
+  }
+}
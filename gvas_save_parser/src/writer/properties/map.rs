@@ -0,0 +1,82 @@
+use super::{r#struct::write_struct_value, text::write_text, write_byte_value};
+use crate::{
+  parser::{ArchiveVersion, MapProperty, MapPropertyKey, MapPropertyValue},
+  writer::primitives::{
+    write_bool, write_f32, write_f64, write_guid, write_i16, write_i32, write_i64, write_i8,
+    write_soft_object_path, write_string, write_u16, write_u32, write_u64,
+  },
+};
+
+pub fn write_map_property(
+  tag: &mut Vec<u8>,
+  value: &mut Vec<u8>,
+  map: &MapProperty,
+  version: ArchiveVersion,
+) {
+  write_string(tag, &map.key_type);
+  write_string(tag, &map.value_type);
+
+  write_u32(value, map.keys_to_remove.len() as u32);
+  for k in &map.keys_to_remove {
+    write_map_key(value, k, version);
+  }
+
+  write_u32(value, map.entries.len() as u32);
+  for (k, v) in &map.entries {
+    write_map_key(value, k, version);
+    write_map_value(value, v, version);
+  }
+}
+
+fn write_map_key(out: &mut Vec<u8>, key: &MapPropertyKey, version: ArchiveVersion) {
+  match key {
+    MapPropertyKey::Struct(s) => write_struct_value(out, s, version),
+    MapPropertyKey::Int8(i) => write_i8(out, *i),
+    MapPropertyKey::Int16(i) => write_i16(out, *i),
+    MapPropertyKey::Int(i) => write_i32(out, *i),
+    MapPropertyKey::Int64(i) => write_i64(out, *i),
+    MapPropertyKey::UInt16(i) => write_u16(out, *i),
+    MapPropertyKey::UInt32(i) => write_u32(out, *i),
+    MapPropertyKey::UInt64(i) => write_u64(out, *i),
+    MapPropertyKey::Float(i) => write_f32(out, *i),
+    MapPropertyKey::Double(i) => write_f64(out, *i),
+    MapPropertyKey::Bool(b) => write_bool(out, *b),
+    MapPropertyKey::Byte(b) => write_byte_value(out, b),
+    MapPropertyKey::Str(s)
+    | MapPropertyKey::Name(s)
+    | MapPropertyKey::Enum(s)
+    | MapPropertyKey::Object(s)
+    | MapPropertyKey::Interface(s) => write_string(out, s),
+    MapPropertyKey::SoftObject(p) | MapPropertyKey::SoftClassPath(p) => {
+      write_soft_object_path(out, p)
+    }
+    MapPropertyKey::LazyObject(g) => write_guid(out, g),
+  }
+}
+
+fn write_map_value(out: &mut Vec<u8>, value: &MapPropertyValue, version: ArchiveVersion) {
+  match value {
+    MapPropertyValue::Struct(s) => write_struct_value(out, s, version),
+    MapPropertyValue::Int8(i) => write_i8(out, *i),
+    MapPropertyValue::Int16(i) => write_i16(out, *i),
+    MapPropertyValue::Int(i) => write_i32(out, *i),
+    MapPropertyValue::Int64(i) => write_i64(out, *i),
+    MapPropertyValue::UInt16(i) => write_u16(out, *i),
+    MapPropertyValue::UInt32(i) => write_u32(out, *i),
+    MapPropertyValue::UInt64(i) => write_u64(out, *i),
+    MapPropertyValue::Float(i) => write_f32(out, *i),
+    MapPropertyValue::Double(i) => write_f64(out, *i),
+    MapPropertyValue::Bool(b) => write_bool(out, *b),
+    MapPropertyValue::Byte(b) => write_byte_value(out, b),
+    MapPropertyValue::Str(s)
+    | MapPropertyValue::Name(s)
+    | MapPropertyValue::Enum(s)
+    | MapPropertyValue::Object(s)
+    | MapPropertyValue::Interface(s) => write_string(out, s),
+    MapPropertyValue::Text(t) => write_text(out, t),
+    MapPropertyValue::SoftObject(p) | MapPropertyValue::SoftClassPath(p) => {
+      write_soft_object_path(out, p)
+    }
+    MapPropertyValue::LazyObject(g) => write_guid(out, g),
+  }
+}
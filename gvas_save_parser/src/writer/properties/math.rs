@@ -0,0 +1,69 @@
+use crate::{
+  parser::{
+    ArchiveVersion, BoundingBox, Color, IntPoint, IntVector, LinearColor, Quat, Rotator, Vector,
+    Vector2D, Vector4,
+  },
+  writer::primitives::{write_bool, write_f32, write_f64, write_i32},
+};
+
+/**
+ * Components are written back out at the width the save uses, see
+ * `parse_component`.
+ */
+fn write_components<const N: usize>(
+  out: &mut Vec<u8>,
+  components: [f64; N],
+  version: ArchiveVersion,
+) {
+  for c in components {
+    if version.large_world_coordinates {
+      write_f64(out, c);
+    } else {
+      write_f32(out, c as f32);
+    }
+  }
+}
+
+pub fn write_vector(out: &mut Vec<u8>, v: &Vector, version: ArchiveVersion) {
+  write_components(out, [v.x, v.y, v.z], version);
+}
+
+pub fn write_vector_2d(out: &mut Vec<u8>, v: &Vector2D, version: ArchiveVersion) {
+  write_components(out, [v.x, v.y], version);
+}
+
+pub fn write_vector_4(out: &mut Vec<u8>, v: &Vector4, version: ArchiveVersion) {
+  write_components(out, [v.x, v.y, v.z, v.w], version);
+}
+
+pub fn write_rotator(out: &mut Vec<u8>, r: &Rotator, version: ArchiveVersion) {
+  write_components(out, [r.pitch, r.yaw, r.roll], version);
+}
+
+pub fn write_quat(out: &mut Vec<u8>, q: &Quat, version: ArchiveVersion) {
+  write_components(out, [q.x, q.y, q.z, q.w], version);
+}
+
+pub fn write_linear_color(out: &mut Vec<u8>, c: &LinearColor) {
+  [c.r, c.g, c.b, c.a]
+    .into_iter()
+    .for_each(|f| write_f32(out, f));
+}
+
+pub fn write_color(out: &mut Vec<u8>, c: &Color) {
+  out.extend_from_slice(&[c.b, c.g, c.r, c.a]);
+}
+
+pub fn write_int_point(out: &mut Vec<u8>, p: &IntPoint) {
+  [p.x, p.y].into_iter().for_each(|i| write_i32(out, i));
+}
+
+pub fn write_int_vector(out: &mut Vec<u8>, v: &IntVector) {
+  [v.x, v.y, v.z].into_iter().for_each(|i| write_i32(out, i));
+}
+
+pub fn write_box(out: &mut Vec<u8>, b: &BoundingBox, version: ArchiveVersion) {
+  write_vector(out, &b.min, version);
+  write_vector(out, &b.max, version);
+  write_bool(out, b.is_valid);
+}
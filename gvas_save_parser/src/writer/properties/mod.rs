@@ -0,0 +1,125 @@
+mod array;
+mod map;
+mod math;
+mod set;
+mod r#struct;
+mod text;
+
+use super::primitives::{
+  write_bool, write_f32, write_f64, write_guid, write_i16, write_i32, write_i64, write_i8,
+  write_soft_object_path, write_string, write_u16, write_u32, write_u64,
+};
+use crate::parser::{
+  ArchiveVersion, BytePropertyValue, Guid, Property, PropertyMap, PropertyTag, TaggedProperty,
+};
+use array::write_array_property;
+use map::write_map_property;
+use r#struct::write_struct_property;
+use set::write_set_property;
+use text::write_text;
+
+pub fn write_property_map(out: &mut Vec<u8>, properties: &PropertyMap, version: ArchiveVersion) {
+  for (name, property) in properties {
+    write_property(out, name, property, version);
+  }
+  write_string(out, "None");
+}
+
+/**
+ * Properties are laid out in the structure:
+ *   [string | name][string | type][u32 | size][u32 | array index][tag][value]
+ * where the size is the length of the value alone, so the value is written
+ * first in order to know the size before writing anything else.
+ */
+fn write_property(
+  out: &mut Vec<u8>,
+  name: &str,
+  property: &TaggedProperty,
+  version: ArchiveVersion,
+) {
+  let mut tag = vec![];
+  let mut value = vec![];
+  write_property_value(
+    &mut tag,
+    &mut value,
+    &property.value,
+    &property.tag,
+    version,
+  );
+
+  write_string(out, name);
+  write_string(out, property.value.type_name());
+  write_u32(out, value.len() as u32);
+  write_u32(out, property.tag.array_index);
+  out.extend_from_slice(&tag);
+  write_property_guid(out, &property.tag.guid);
+  out.extend_from_slice(&value);
+}
+
+pub fn write_property_guid(out: &mut Vec<u8>, guid: &Option<Guid>) {
+  write_bool(out, guid.is_some());
+  if let Some(guid) = guid {
+    write_guid(out, guid);
+  }
+}
+
+/**
+ * Writes the type specific data of the property tag followed by the value of
+ * the property.
+ */
+fn write_property_value(
+  tag: &mut Vec<u8>,
+  value: &mut Vec<u8>,
+  property: &Property,
+  property_tag: &PropertyTag,
+  version: ArchiveVersion,
+) {
+  match property {
+    Property::Int8(i) => write_i8(value, *i),
+    Property::Int16(i) => write_i16(value, *i),
+    Property::Int(i) => write_i32(value, *i),
+    Property::Int64(i) => write_i64(value, *i),
+    Property::UInt16(i) => write_u16(value, *i),
+    Property::UInt32(i) => write_u32(value, *i),
+    Property::UInt64(i) => write_u64(value, *i),
+    Property::Bool(b) => write_bool(tag, *b),
+    Property::Struct(s) => write_struct_property(tag, value, s, property_tag, version),
+    Property::Array(a) => write_array_property(tag, value, a, version),
+    Property::Float(f) => write_f32(value, *f),
+    Property::Double(f) => write_f64(value, *f),
+    Property::MulticastInlineDelegate(delegates) => {
+      write_u32(value, delegates.len() as u32);
+      for delegate in delegates {
+        write_string(value, &delegate.object_path);
+        write_string(value, &delegate.function_name);
+      }
+    }
+    Property::Str(s) | Property::Object(s) | Property::Name(s) | Property::Interface(s) => {
+      write_string(value, s)
+    }
+    Property::Map(m) => write_map_property(tag, value, m, version),
+    Property::Set(s) => write_set_property(tag, value, s, version),
+    Property::Enum { name, value: v } => {
+      write_string(tag, name);
+      write_string(value, v);
+    }
+    Property::Byte { name, value: v } => {
+      write_string(tag, name);
+      write_byte_value(value, v);
+    }
+    Property::Text(t) => write_text(value, t),
+    Property::SoftObject(p) | Property::SoftClassPath(p) => write_soft_object_path(value, p),
+    Property::LazyObject(g) => write_guid(value, g),
+    Property::Unknown { tag_data, raw, .. } => {
+      tag.extend_from_slice(tag_data);
+      value.extend_from_slice(raw);
+    }
+  }
+}
+
+pub fn write_byte_value(out: &mut Vec<u8>, value: &BytePropertyValue) {
+  match value {
+    BytePropertyValue::Byte(b) => out.push(*b),
+    BytePropertyValue::Label(s) => write_string(out, s),
+  }
+}
@@ -0,0 +1,44 @@
+use super::{r#struct::write_struct_value, write_byte_value};
+use crate::{
+  parser::{ArchiveVersion, SetProperty, SetPropertyValue},
+  writer::primitives::{
+    write_f32, write_f64, write_i16, write_i32, write_i64, write_i8, write_string, write_u16,
+    write_u32, write_u64,
+  },
+};
+
+pub fn write_set_property(
+  tag: &mut Vec<u8>,
+  value: &mut Vec<u8>,
+  set: &SetProperty,
+  version: ArchiveVersion,
+) {
+  write_string(tag, &set.element_type);
+
+  for elements in [&set.elements_to_remove, &set.elements] {
+    write_u32(value, elements.len() as u32);
+    for element in elements {
+      write_set_element(value, element, version);
+    }
+  }
+}
+
+fn write_set_element(out: &mut Vec<u8>, element: &SetPropertyValue, version: ArchiveVersion) {
+  match element {
+    SetPropertyValue::Struct(s) => write_struct_value(out, s, version),
+    SetPropertyValue::Int8(i) => write_i8(out, *i),
+    SetPropertyValue::Int16(i) => write_i16(out, *i),
+    SetPropertyValue::Int(i) => write_i32(out, *i),
+    SetPropertyValue::Int64(i) => write_i64(out, *i),
+    SetPropertyValue::UInt16(i) => write_u16(out, *i),
+    SetPropertyValue::UInt32(i) => write_u32(out, *i),
+    SetPropertyValue::UInt64(i) => write_u64(out, *i),
+    SetPropertyValue::Float(i) => write_f32(out, *i),
+    SetPropertyValue::Double(i) => write_f64(out, *i),
+    SetPropertyValue::Byte(b) => write_byte_value(out, b),
+    SetPropertyValue::Str(s)
+    | SetPropertyValue::Name(s)
+    | SetPropertyValue::Enum(s)
+    | SetPropertyValue::Object(s) => write_string(out, s),
+  }
+}
@@ -0,0 +1,47 @@
+use super::{
+  math::{
+    write_box, write_color, write_int_point, write_int_vector, write_linear_color, write_quat,
+    write_rotator, write_vector, write_vector_2d, write_vector_4,
+  },
+  write_property_map,
+};
+use crate::{
+  parser::{ArchiveVersion, PropertyTag, StructPropertyValue},
+  writer::primitives::{write_guid, write_i64, write_string},
+};
+
+pub fn write_struct_property(
+  tag: &mut Vec<u8>,
+  value: &mut Vec<u8>,
+  s: &StructPropertyValue,
+  property_tag: &PropertyTag,
+  version: ArchiveVersion,
+) {
+  // Untyped structs are only read from maps and sets, which don't record the
+  // struct type, so there is no type to write for one given as a property
+  write_string(tag, s.struct_type().unwrap_or_default());
+  write_guid(tag, &property_tag.struct_guid.unwrap_or_default());
+  write_struct_value(value, s, version);
+}
+
+pub fn write_struct_value(out: &mut Vec<u8>, s: &StructPropertyValue, version: ArchiveVersion) {
+  match s {
+    StructPropertyValue::DateTime(v) => write_i64(out, *v),
+    StructPropertyValue::Guid(v) => write_guid(out, v),
+    StructPropertyValue::Vector(v) => write_vector(out, v, version),
+    StructPropertyValue::Vector2D(v) => write_vector_2d(out, v, version),
+    StructPropertyValue::Vector4(v) => write_vector_4(out, v, version),
+    StructPropertyValue::Rotator(v) => write_rotator(out, v, version),
+    StructPropertyValue::Quat(v) => write_quat(out, v, version),
+    StructPropertyValue::LinearColor(v) => write_linear_color(out, v),
+    StructPropertyValue::Color(v) => write_color(out, v),
+    StructPropertyValue::IntPoint(v) => write_int_point(out, v),
+    StructPropertyValue::IntVector(v) => write_int_vector(out, v),
+    StructPropertyValue::Box(v) => write_box(out, v, version),
+    StructPropertyValue::Timespan(v) => write_i64(out, *v),
+    StructPropertyValue::Transform(v) => write_property_map(out, &v.to_properties(), version),
+    StructPropertyValue::Foreign { value, .. } | StructPropertyValue::Untyped(value) => {
+      write_property_map(out, value, version)
+    }
+  }
+}
@@ -0,0 +1,167 @@
+use crate::{
+  parser::{FText, FormatArgumentValue, NumberFormattingOptions, TextHistory},
+  writer::primitives::{
+    write_f32, write_f64, write_i32, write_i64, write_i8, write_string, write_u32, write_u64,
+  },
+};
+
+pub fn write_text(out: &mut Vec<u8>, text: &FText) {
+  write_u32(out, text.flags);
+  write_i8(out, text.history.history_type());
+  match &text.history {
+    TextHistory::None {
+      culture_invariant_string,
+    } => {
+      write_bool32(out, culture_invariant_string.is_some());
+      if let Some(s) = culture_invariant_string {
+        write_string(out, s);
+      }
+    }
+    TextHistory::Base {
+      namespace,
+      key,
+      source_string,
+    } => {
+      write_string(out, namespace);
+      write_string(out, key);
+      write_string(out, source_string);
+    }
+    TextHistory::NamedFormat {
+      source_format,
+      arguments,
+    }
+    | TextHistory::ArgumentFormat {
+      source_format,
+      arguments,
+    } => {
+      write_text(out, source_format);
+      write_u32(out, arguments.len() as u32);
+      for (name, argument) in arguments {
+        write_string(out, name);
+        write_format_argument_value(out, argument);
+      }
+    }
+    TextHistory::OrderedFormat {
+      source_format,
+      arguments,
+    } => {
+      write_text(out, source_format);
+      write_u32(out, arguments.len() as u32);
+      for argument in arguments {
+        write_format_argument_value(out, argument);
+      }
+    }
+    TextHistory::AsNumber {
+      source_value,
+      format_options,
+      target_culture,
+    }
+    | TextHistory::AsPercent {
+      source_value,
+      format_options,
+      target_culture,
+    } => {
+      write_format_argument_value(out, source_value);
+      write_number_formatting_options(out, format_options);
+      write_string(out, target_culture);
+    }
+    TextHistory::AsCurrency {
+      currency_code,
+      source_value,
+      format_options,
+      target_culture,
+    } => {
+      write_string(out, currency_code);
+      write_format_argument_value(out, source_value);
+      write_number_formatting_options(out, format_options);
+      write_string(out, target_culture);
+    }
+    TextHistory::AsDate {
+      source_date_time,
+      date_style: style,
+      time_zone,
+      target_culture,
+    }
+    | TextHistory::AsTime {
+      source_date_time,
+      time_style: style,
+      time_zone,
+      target_culture,
+    } => {
+      write_i64(out, *source_date_time);
+      write_i8(out, *style);
+      write_string(out, time_zone);
+      write_string(out, target_culture);
+    }
+    TextHistory::AsDateTime {
+      source_date_time,
+      date_style,
+      time_style,
+      time_zone,
+      target_culture,
+    } => {
+      write_i64(out, *source_date_time);
+      write_i8(out, *date_style);
+      write_i8(out, *time_style);
+      write_string(out, time_zone);
+      write_string(out, target_culture);
+    }
+    TextHistory::Transform {
+      source_text,
+      transform_type,
+    } => {
+      write_text(out, source_text);
+      out.push(*transform_type);
+    }
+    TextHistory::StringTableEntry { table_id, key } => {
+      write_string(out, table_id);
+      write_string(out, key);
+    }
+  }
+}
+
+fn write_format_argument_value(out: &mut Vec<u8>, value: &FormatArgumentValue) {
+  match value {
+    FormatArgumentValue::Int(i) => {
+      write_i8(out, 0);
+      write_i64(out, *i);
+    }
+    FormatArgumentValue::UInt(i) => {
+      write_i8(out, 1);
+      write_u64(out, *i);
+    }
+    FormatArgumentValue::Float(f) => {
+      write_i8(out, 2);
+      write_f32(out, *f);
+    }
+    FormatArgumentValue::Double(f) => {
+      write_i8(out, 3);
+      write_f64(out, *f);
+    }
+    FormatArgumentValue::Text(t) => {
+      write_i8(out, 4);
+      write_text(out, t);
+    }
+    FormatArgumentValue::Gender(g) => {
+      write_i8(out, 5);
+      out.push(*g);
+    }
+  }
+}
+
+fn write_number_formatting_options(out: &mut Vec<u8>, options: &Option<NumberFormattingOptions>) {
+  write_bool32(out, options.is_some());
+  if let Some(options) = options {
+    write_bool32(out, options.always_sign);
+    write_bool32(out, options.use_grouping);
+    write_i8(out, options.rounding_mode);
+    write_i32(out, options.minimum_integral_digits);
+    write_i32(out, options.maximum_integral_digits);
+    write_i32(out, options.minimum_fractional_digits);
+    write_i32(out, options.maximum_fractional_digits);
+  }
+}
+
+fn write_bool32(out: &mut Vec<u8>, b: bool) {
+  write_u32(out, b as u32);
+}